@@ -0,0 +1,81 @@
+#![warn(clippy::all, rust_2018_idioms)]
+
+// the text adventure in a terminal, build without the egui apps with `cargo run --bin adventure --no-default-features`
+
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+use eframe_template::game::text::StyledLine;
+use eframe_template::game::Game;
+
+const USAGE: &str = "\
+usage: adventure [options]
+
+options:
+    --seed <n>   seed for every roll (default 0)
+    --plain      print without colours";
+
+fn print(line: &StyledLine, plain: bool) {
+    if plain {
+        println!("{}", line.text());
+    } else {
+        println!("{}", line.to_ansi());
+    }
+}
+
+fn main() -> ExitCode {
+    let mut seed = 0;
+    let mut plain = std::env::var_os("NO_COLOR").is_some();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => seed = value,
+                None => {
+                    eprintln!("error: --seed needs a number\n\n{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--plain" => plain = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => {
+                eprintln!("error: unexpected argument {arg}\n\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let mut game = Game::new(seed);
+    print(&"Type help to list the commands, or quit to leave.".into(), plain);
+    for line in game.run("look") {
+        print(&line, plain);
+    }
+
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush().ok();
+
+        let mut input = String::new();
+        match stdin.lock().read_line(&mut input) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(err) => {
+                eprintln!("error: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+
+        if matches!(input.trim(), "quit" | "exit") {
+            break;
+        }
+        for line in game.run(&input) {
+            print(&line, plain);
+        }
+    }
+    ExitCode::SUCCESS
+}
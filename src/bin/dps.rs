@@ -0,0 +1,171 @@
+#![warn(clippy::all, rust_2018_idioms)]
+
+// headless dps calculator, build without the egui apps with `cargo run --bin dps --no-default-features -- <build.ron>`
+
+use std::process::ExitCode;
+
+use eframe_template::game::build::Build;
+use eframe_template::game::dps::*;
+use eframe_template::game::item::Rarity;
+use eframe_template::game::monster::Bestiary;
+use eframe_template::game::sim::{Encounter, Side};
+use eframe_template::game::Player;
+
+const USAGE: &str = "\
+usage: dps <build.ron> [options]
+
+options:
+    --attacks <n>        roll n attacks without a defender (default 10000)
+    --encounter <name>   fight a monster instead of rolling attacks
+    --level <n>          level of the monster (default 1)
+    --rarity <rarity>    normal, magic, rare or unique (default normal)
+    --time <seconds>     longest an encounter can last (default 300)
+    --seed <n>           seed for every roll (default 0)
+    --json               print the summary as json";
+
+struct Options {
+    build: String,
+    attacks: usize,
+    encounter: Option<String>,
+    level: i32,
+    rarity: Rarity,
+    time: i32,
+    seed: u64,
+    json: bool,
+}
+
+// None if help was asked for
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut build = None;
+    let mut options = Options {
+        build: String::new(),
+        attacks: 10_000,
+        encounter: None,
+        level: 1,
+        rarity: Rarity::Normal,
+        time: 300,
+        seed: 0,
+        json: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{name} needs a value"));
+        match arg.as_str() {
+            "--attacks" => options.attacks = parse(&value("--attacks")?)?,
+            "--encounter" => options.encounter = Some(value("--encounter")?),
+            "--level" => options.level = parse(&value("--level")?)?,
+            "--rarity" => options.rarity = parse_rarity(&value("--rarity")?)?,
+            "--time" => options.time = parse_seconds(&value("--time")?)?,
+            "--seed" => options.seed = parse(&value("--seed")?)?,
+            "--json" => options.json = true,
+            "-h" | "--help" => return Ok(None),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ if build.is_none() => build = Some(arg),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+
+    options.build = build.ok_or("missing build file")?;
+    Ok(Some(options))
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid number {value}"))
+}
+
+// encounters are timed in milliseconds, so the time has to fit once it is multiplied by 1000
+fn parse_seconds(value: &str) -> Result<i32, String> {
+    let seconds: i32 = parse(value)?;
+    match seconds.checked_mul(1000) {
+        Some(_) if seconds > 0 => Ok(seconds),
+        _ => Err(format!("--time has to be between 1 and {} seconds", i32::MAX / 1000)),
+    }
+}
+
+fn parse_rarity(value: &str) -> Result<Rarity, String> {
+    [Rarity::Normal, Rarity::Magic, Rarity::Rare, Rarity::Unique]
+        .into_iter()
+        .find(|rarity| rarity.name().eq_ignore_ascii_case(value))
+        .ok_or(format!("unknown rarity {value}"))
+}
+
+fn run(options: &Options) -> Result<(String, DpsSummary), String> {
+    let text = std::fs::read_to_string(&options.build).map_err(|err| format!("can't read {}: {err}", options.build))?;
+    let build = Build::from_ron(&text).map_err(|err| err.to_string())?;
+    let mut player = build.player().map_err(|err| err.to_string())?;
+
+    let Some(name) = &options.encounter else {
+        let title = format!("{}, {} attacks", attack_name(&player), options.attacks);
+        return Ok((title, simulate_attacks(&player, options.attacks, options.seed)));
+    };
+
+    let bestiary = Bestiary::builtin();
+    let def = bestiary.get(name).ok_or(format!("unknown monster {name}"))?;
+    let mut monster = def.spawn(options.level, options.rarity);
+
+    let mut encounter = Encounter::new(&mut player, &mut monster, options.seed);
+    let winner = encounter.run(options.time * 1000);
+    let outcome = match winner {
+        Some(Side::Left) => "won",
+        Some(Side::Right) => "lost",
+        None => "ran out of time",
+    };
+
+    let title = format!(
+        "{} against a level {} {} {}, {}",
+        attack_name(encounter.left),
+        options.level,
+        options.rarity.name().to_lowercase(),
+        name,
+        outcome,
+    );
+    Ok((title, summarize_encounter(&encounter, Side::Left)))
+}
+
+// the main skill, or the weapon for builds without one
+fn attack_name(player: &Player) -> &str {
+    player.main_skill().map_or(player.main_hand_name(), |skill| &skill.name)
+}
+
+fn print_text(title: &str, summary: &DpsSummary, seed: u64) {
+    println!("{title} (seed {seed})");
+    println!("  duration     {:>10.2}s", summary.duration as f32 / 1000.0);
+    println!("  attacks      {:>10}", summary.attacks);
+    println!("  hit rate     {:>10.2}%", summary.hit_rate);
+    println!("  crit rate    {:>10.2}%", summary.crit_rate);
+    println!("  average hit  {:>10.1}", summary.average_hit);
+    println!("  dps          {:>10.1}", summary.dps);
+    println!("  damage per hit:");
+    for (percentile, damage) in &summary.percentiles {
+        println!("    p{percentile:<3}       {damage:>10}");
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&options) {
+        Ok((_, summary)) if options.json => {
+            println!("{}", serde_json::to_string_pretty(&summary).expect("summary should serialize"));
+            ExitCode::SUCCESS
+        }
+        Ok((title, summary)) => {
+            print_text(&title, &summary, options.seed);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,142 +1,1038 @@
+pub mod affix;
+pub mod ailment;
+pub mod buff;
+pub mod build;
+pub mod combat;
+pub mod command;
+pub mod console;
+pub mod damage;
+pub mod debuff;
+pub mod dps;
+pub mod inventory;
+pub mod item;
+pub mod modifier;
+pub mod monster;
+pub mod passive;
+pub mod sim;
+pub mod skill;
 pub mod stats;
+pub mod text;
+pub mod world;
 
-use std::ops::Range;
 use crate::Rand;
-use rand::prelude::*;
+use rand::SeedableRng;
 
+use ailment::*;
+use buff::*;
+use combat::*;
+use command::*;
+use debuff::*;
+use inventory::*;
+use item::*;
+use modifier::*;
+use passive::*;
+use skill::*;
 use stats::*;
+use text::*;
+use world::*;
 
+// the text adventure, everything the console commands act on
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct Game {
+    pub player: Player,
+    world: World,
+    room: RoomId,
+    rand: Rand,
+}
+
+impl Game {
+    pub fn new(seed: u64) -> Self {
+        let mut rand = Rand::seed_from_u64(seed);
+        let world = World::builtin(&mut rand);
+        Self {
+            player: Player::new(),
+            room: world.start,
+            world,
+            rand,
+        }
+    }
+
+    pub fn room(&self) -> &Room {
+        self.world.room(self.room)
+    }
+
+    pub fn zone(&self) -> &Zone {
+        self.world.zone(self.room)
+    }
 
+    // parse and execute a line typed into the console, returning what to print
+    pub fn run(&mut self, line: &str) -> Vec<StyledLine> {
+        match Command::parse(line) {
+            Ok(command) => self.execute(command),
+            Err(err) => vec![err.to_string().into()],
+        }
+    }
+
+    pub fn execute(&mut self, command: Command) -> Vec<StyledLine> {
+        match command {
+            Command::Help => VERBS.iter().map(|(verb, help)| format!("{verb:<18} {help}").into()).collect(),
+            Command::Look => self.look(),
+            Command::Go(direction) => self.go(direction),
+            Command::Attack(target) => self.attack(&target),
+            Command::Take(target) => self.take(&target),
+            Command::Equip(target) => self.equip(&target),
+            Command::Unequip(target) => self.unequip(&target),
+            Command::Inventory => self.list_inventory(),
+            Command::Stats => self.describe_player(),
+        }
+    }
+
+    fn look(&self) -> Vec<StyledLine> {
+        let room = self.room();
+        let zone = self.world.zone(self.room);
+        let header = StyledLine::header(&room.name).with(format!(" - {}, area level {}", zone.name, zone.level), SpanStyle::Plain);
+        let mut lines = vec![header, room.description.as_str().into()];
+
+        let exits = room.exits.iter().map(|(direction, _)| direction.name()).collect::<Vec<_>>();
+        if exits.is_empty() {
+            lines.push("There is no way out.".into());
+        } else {
+            lines.push(format!("Exits: {}.", exits.join(", ")).into());
+        }
+        for monster in &room.monsters {
+            let name = SpanStyle::Rarity(monster.rarity).markup(&monster.name);
+            lines.push(StyledLine::parse(&format!("A level {} {name} is here.", monster.level)));
+        }
+        for item in &room.items {
+            lines.push(StyledLine::parse(&format!("{} lies on the ground.", item_name(item))));
+        }
+        lines
+    }
+
+    fn go(&mut self, direction: Direction) -> Vec<StyledLine> {
+        let Some(room) = self.room().exit(direction) else {
+            return vec![format!("You can't go {}.", direction.name()).into()];
+        };
+        self.room = room;
+        self.look()
+    }
+
+    fn attack(&mut self, target: &str) -> Vec<StyledLine> {
+        let room = self.world.room_mut(self.room);
+        let Some(index) = find(&room.monsters, |monster| &monster.name, target) else {
+            return vec![format!("There is no {target} here.").into()];
+        };
+        let monster = &mut room.monsters[index];
+        let mut lines = Vec::new();
+
+        let report = resolve_hit(&self.player, monster, &mut self.rand);
+        lines.push(StyledLine::parse(&report.describe("you", &format!("the {}", monster.name), Perspective::Attacker)));
+        if monster.pools().health <= 0 {
+            lines.push(format!("The {} dies.", monster.name).into());
+            room.monsters.remove(index);
+            return lines;
+        }
+
+        let report = resolve_hit(monster, &mut self.player, &mut self.rand);
+        lines.push(StyledLine::parse(&report.describe(&format!("the {}", monster.name), "you", Perspective::Defender)));
+        if self.player.pools().health <= 0 {
+            lines.push("You die, and wake up back at camp.".into());
+            self.player.restore_pools();
+            self.player.ailments_mut().clear();
+            self.player.debuffs_mut().clear();
+            self.room = self.world.start;
+        }
+        lines
+    }
+
+    fn take(&mut self, target: &str) -> Vec<StyledLine> {
+        let room = self.world.room_mut(self.room);
+        let Some(index) = find(&room.items, |item| &item.name, target) else {
+            return vec![format!("There is no {target} here.").into()];
+        };
+        let fits = self.player.inventory().room_for(&room.items[index]);
+        if fits == 0 {
+            return vec![StyledLine::parse(&format!("There is no room in your pack for the {}.", item_name(&room.items[index])))];
+        }
+
+        // as much of a stack as fits is picked up, the rest stays on the ground
+        let mut item = room.items.remove(index);
+        let line = if fits < item.stack {
+            let mut left = item.clone();
+            left.stack = item.stack - fits;
+            item.stack = fits;
+            let line = format!("You pick up the {} (no room for {}).", item_name(&item), left.stack);
+            room.items.insert(index, left);
+            line
+        } else {
+            format!("You pick up the {}.", item_name(&item))
+        };
+        self.player.inventory_mut().add(item).expect("only as much as fits is picked up");
+        vec![StyledLine::parse(&line)]
+    }
+
+    fn equip(&mut self, target: &str) -> Vec<StyledLine> {
+        let Some(index) = find(self.player.inventory().items(), |item| &item.name, target) else {
+            return vec![format!("You aren't carrying a {target}.").into()];
+        };
+        let item = &self.player.inventory().items()[index];
+        let Some(slot) = self.player.slot_for(item.class) else {
+            return vec![StyledLine::parse(&format!("You can't equip the {}.", item_name(item)))];
+        };
+        let mut lines = vec![StyledLine::parse(&format!(
+            "You equip the {} in your {} slot.",
+            item_name(item),
+            slot.name().to_lowercase(),
+        ))];
+
+        // the equipped item leaves the inventory and whatever it replaced is added to the end
+        let kept = self.player.inventory().len() - 1;
+        if let Err(err) = self.player.equip(index, slot) {
+            return vec![err.to_string().into()];
+        }
+        for previous in &self.player.inventory().items()[kept..] {
+            lines.push(StyledLine::parse(&format!("You put the {} in your pack.", item_name(previous))));
+        }
+        lines
+    }
+
+    fn unequip(&mut self, target: &str) -> Vec<StyledLine> {
+        let equipped = EquipSlot::ALL
+            .into_iter()
+            .filter_map(|slot| Some((slot, self.player.slot(slot)?)))
+            .collect::<Vec<_>>();
+        let Some(index) = find(&equipped, |(_, item)| &item.name, target) else {
+            return vec![format!("You aren't wearing a {target}.").into()];
+        };
+        let (slot, item) = equipped[index];
+        let line = format!("You put the {} in your pack.", item_name(item));
+
+        match self.player.unequip(slot) {
+            Ok(()) => vec![StyledLine::parse(&line)],
+            Err(err) => vec![err.to_string().into()],
+        }
+    }
+
+    fn list_inventory(&self) -> Vec<StyledLine> {
+        let inventory = self.player.inventory();
+        if inventory.is_empty() {
+            return vec!["You aren't carrying anything.".into()];
+        }
+        let mut lines = vec![StyledLine::header(format!("Inventory {}/{}", inventory.len(), inventory.capacity()))];
+        lines.extend(inventory.items().iter().map(|item| StyledLine::parse(&format!("  {}", item_name(item)))));
+        lines
+    }
+
+    fn describe_player(&self) -> Vec<StyledLine> {
+        let pools = self.player.pools();
+        let max = self.player.max_pools();
+        let mut lines = vec![
+            StyledLine::header("Character"),
+            format!(
+                "Health {}/{}, mana {}/{}, energy shield {}/{}",
+                pools.health, max.health, pools.mana, max.mana, pools.energy_shield, max.energy_shield,
+            ).into(),
+        ];
+        for slot in EquipSlot::ALL {
+            if let Some(item) = self.player.slot(slot) {
+                lines.push(StyledLine::parse(&format!("  {:<12} {}", slot.name(), item_name(item))));
+            }
+        }
+        lines
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+// the first thing whose name contains `target`, ignoring case
+fn find<T>(things: &[T], name: impl Fn(&T) -> &String, target: &str) -> Option<usize> {
+    let target = target.to_lowercase();
+    things.iter().position(|thing| name(thing).to_lowercase().contains(&target))
 }
 
+// the item's name as markup, in its rarity's colour, stacks show how many there are
+fn item_name(item: &Item) -> String {
+    let name = SpanStyle::Rarity(item.rarity).markup(&item.name);
+    match item.stack {
+        1 => name,
+        stack => format!("{name} x{stack}"),
+    }
+}
+
+// everything that can contribute to a player's final stats
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StatSource {
+    Base,
+    Equipment(EquipSlot),
+    Passives,
+    Buff(String),
+    Skill(String),
+}
+
+impl StatSource {
+    pub fn name(&self) -> &str {
+        match self {
+            StatSource::Base => "Base",
+            StatSource::Equipment(slot) => slot.name(),
+            StatSource::Passives => "Passives",
+            StatSource::Buff(name) => name,
+            StatSource::Skill(name) => name,
+        }
+    }
+}
+
+// the final stats aren't saved, they are recalculated from their sources on load
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(from = "SavedPlayer")]
 pub struct Player {
-    health: i32,
-    mana: i32,
-    energy_shield: i32,
-
-    pub stats: StatMap,
-
-    main_hand: Item,
-    off_hand: Item,
-
-    helmet: Item,
-    body_armour: Item,
-    gloves: Item,
-    boots: Item,
-    left_ring: Item,
-    right_ring: Item,
-    amulet: Item,
+    pools: Pools,
+    ailments: Ailments,
+    debuffs: Debuffs,
+
+    // final stats, recalculated from the sources below whenever one of them changes
+    #[serde(skip)]
+    stats: StatMap,
+    #[serde(skip)]
+    modifiers: Modifiers,
+
+    base_stats: StatMap,
+    passive_tree: PassiveTree,
+    buffs: Vec<Buff>,
+    #[serde(default)]
+    skills: SkillBar,
+    #[serde(default)]
+    inventory: Inventory,
+
+    main_hand: Option<Item>,
+    off_hand: Option<Item>,
+
+    helmet: Option<Item>,
+    body_armour: Option<Item>,
+    gloves: Option<Item>,
+    boots: Option<Item>,
+    left_ring: Option<Item>,
+    right_ring: Option<Item>,
+    amulet: Option<Item>,
+}
+
+#[derive(serde::Deserialize)]
+struct SavedPlayer {
+    pools: Pools,
+    ailments: Ailments,
+    debuffs: Debuffs,
+    base_stats: StatMap,
+    passive_tree: PassiveTree,
+    buffs: Vec<Buff>,
+    #[serde(default)]
+    skills: SkillBar,
+    #[serde(default)]
+    inventory: Inventory,
+    main_hand: Option<Item>,
+    off_hand: Option<Item>,
+    helmet: Option<Item>,
+    body_armour: Option<Item>,
+    gloves: Option<Item>,
+    boots: Option<Item>,
+    left_ring: Option<Item>,
+    right_ring: Option<Item>,
+    amulet: Option<Item>,
 }
 
-/*
-// attacks per second = 1 / (attack_time / 1000)
-let weapon_attack_time = 1000_i32;
-*/
+impl From<SavedPlayer> for Player {
+    fn from(saved: SavedPlayer) -> Self {
+        let mut player = Self {
+            pools: saved.pools,
+            ailments: saved.ailments,
+            debuffs: saved.debuffs,
+            stats: StatMap::new(),
+            modifiers: Modifiers::default(),
+            base_stats: saved.base_stats,
+            passive_tree: saved.passive_tree,
+            buffs: saved.buffs,
+            skills: saved.skills,
+            inventory: saved.inventory,
+            main_hand: saved.main_hand,
+            off_hand: saved.off_hand,
+            helmet: saved.helmet,
+            body_armour: saved.body_armour,
+            gloves: saved.gloves,
+            boots: saved.boots,
+            left_ring: saved.left_ring,
+            right_ring: saved.right_ring,
+            amulet: saved.amulet,
+        };
+        player.recalculate();
+        player
+    }
+}
 
-const CRIT_CHANCE_MAX_VALUE: i32 = 100 * 100; 
+pub const CRIT_CHANCE_MAX_VALUE: i32 = 100 * 100; 
 
 impl Player {
     pub fn new() -> Self {
+        let mut base_stats = StatMap::new();
+        base_stats.add(MaxHealth, 100);
+        base_stats.add(MaxMana, 100);
+        base_stats.add(MaxEnergyShield, 100);
+        base_stats.add(MaxSpirit, 100);
+        base_stats.add(Accuracy, 50);
+
+        let mut player = Self {
+            pools: Pools {
+                health: 0,
+                mana: 0,
+                energy_shield: 0,
+            },
+            ailments: Ailments::default(),
+            debuffs: Debuffs::default(),
+            stats: StatMap::new(),
+            modifiers: Modifiers::default(),
+            base_stats,
+            passive_tree: PassiveTree::builtin(),
+            buffs: Vec::new(),
+            skills: SkillBar::default(),
+            inventory: Inventory::default(),
+            main_hand: Some(Item::new_weapon("Short Sword", ItemClass::Sword, 1, WeaponBase::physical(15, 24, 1000, 500))),
+            off_hand: None,
+            helmet: None,
+            body_armour: None,
+            gloves: None,
+            boots: None,
+            left_ring: None,
+            right_ring: None,
+            amulet: None,
+        };
+        player.recalculate();
+        player.restore_pools();
+        player
+    }
+
+    pub fn stats(&self) -> &StatMap {
+        &self.stats
+    }
+
+    pub fn base_stat(&self, stat: Stat) -> i32 {
+        self.base_stats.get(stat)
+    }
+
+    pub fn set_base_stat(&mut self, stat: Stat, value: i32) {
+        self.base_stats.reset(stat);
+        self.base_stats.add(stat, value);
+        self.recalculate();
+    }
+
+    pub fn passive_tree(&self) -> &PassiveTree {
+        &self.passive_tree
+    }
+
+    pub fn allocate_passive(&mut self, id: NodeId) -> Result<(), PassiveError> {
+        self.passive_tree.allocate(id)?;
+        self.recalculate();
+        Ok(())
+    }
+
+    // allocate several nodes at once, in any order
+    pub fn allocate_passives(&mut self, ids: &[NodeId]) -> Result<(), PassiveError> {
+        let result = self.passive_tree.allocate_all(ids);
+        self.recalculate();
+        result
+    }
+
+    pub fn refund_passive(&mut self, id: NodeId) -> Result<(), PassiveError> {
+        self.passive_tree.refund(id)?;
+        self.recalculate();
+        Ok(())
+    }
+
+    pub fn reset_passives(&mut self) {
+        self.passive_tree.reset();
+        self.recalculate();
+    }
+
+    pub fn buffs(&self) -> &[Buff] {
+        &self.buffs
+    }
+
+    // adding a buff with the same name as an existing one refreshes it
+    pub fn add_buff(&mut self, buff: Buff) {
+        self.buffs.retain(|other| other.name != buff.name);
+        self.buffs.push(buff);
+        self.recalculate();
+    }
+
+    pub fn remove_buff(&mut self, name: &str) {
+        self.buffs.retain(|buff| buff.name != name);
+        self.recalculate();
+    }
+
+    // count down buff durations, removing the ones that expired
+    pub fn tick_buffs(&mut self, elapsed: i32) {
+        for buff in &mut self.buffs {
+            if let Some(remaining) = &mut buff.remaining {
+                *remaining -= elapsed;
+            }
+        }
+
+        if self.buffs.iter().any(Buff::is_expired) {
+            self.buffs.retain(|buff| !buff.is_expired());
+            self.recalculate();
+        }
+    }
+
+    pub fn skill_bar(&self) -> &SkillBar {
+        &self.skills
+    }
+
+    // put `skill` in a skill bar slot, returning the skill that was there before
+    pub fn set_skill(&mut self, slot: usize, skill: Option<Skill>) -> Result<Option<Skill>, SkillError> {
+        let previous = self.skills.set(slot, skill)?;
+        self.recalculate();
+        Ok(previous)
+    }
+
+    pub fn tick_skills(&mut self, elapsed: i32) {
+        self.skills.tick(elapsed);
+    }
+
+    // spirit that isn't reserved by an active persistent skill
+    pub fn unreserved_spirit(&self) -> i32 {
+        stat_value(self, MaxSpirit) - self.skills.reserved()
+    }
+
+    // pay for the skill in `slot` and start its cooldown, returning what it hits with,
+    // persistent skills are toggled instead and return None
+    pub fn use_skill(&mut self, slot: usize) -> Result<Option<HitSource>, SkillError> {
+        let unreserved = self.unreserved_spirit();
+        let weapon = self.main_hand_weapon();
+        let mana = self.pools.mana;
+
+        let skill_slot = self.skills.get_mut(slot).ok_or(SkillError::EmptySlot(slot))?;
+        let skill = &skill_slot.skill;
+
+        if skill.is_persistent() {
+            if !skill_slot.active && skill.reservation > unreserved {
+                return Err(SkillError::NotEnoughSpirit {
+                    reservation: skill.reservation,
+                    unreserved,
+                });
+            }
+            skill_slot.active = !skill_slot.active;
+            self.recalculate();
+            return Ok(None);
+        }
+
+        if skill_slot.cooldown > 0 {
+            return Err(SkillError::OnCooldown(skill_slot.cooldown));
+        }
+        if skill.mana_cost > mana {
+            return Err(SkillError::NotEnoughMana {
+                cost: skill.mana_cost,
+                mana,
+            });
+        }
+
+        skill_slot.cooldown = skill.cooldown_time(&self.stats, &self.modifiers);
+        self.pools.mana -= skill.mana_cost;
+        Ok(skill.hit_source(&weapon))
+    }
+
+    pub fn slot(&self, slot: EquipSlot) -> Option<&Item> {
+        match slot {
+            EquipSlot::MainHand => self.main_hand.as_ref(),
+            EquipSlot::OffHand => self.off_hand.as_ref(),
+            EquipSlot::Helmet => self.helmet.as_ref(),
+            EquipSlot::BodyArmour => self.body_armour.as_ref(),
+            EquipSlot::Gloves => self.gloves.as_ref(),
+            EquipSlot::Boots => self.boots.as_ref(),
+            EquipSlot::LeftRing => self.left_ring.as_ref(),
+            EquipSlot::RightRing => self.right_ring.as_ref(),
+            EquipSlot::Amulet => self.amulet.as_ref(),
+        }
+    }
+
+    fn slot_mut(&mut self, slot: EquipSlot) -> &mut Option<Item> {
+        match slot {
+            EquipSlot::MainHand => &mut self.main_hand,
+            EquipSlot::OffHand => &mut self.off_hand,
+            EquipSlot::Helmet => &mut self.helmet,
+            EquipSlot::BodyArmour => &mut self.body_armour,
+            EquipSlot::Gloves => &mut self.gloves,
+            EquipSlot::Boots => &mut self.boots,
+            EquipSlot::LeftRing => &mut self.left_ring,
+            EquipSlot::RightRing => &mut self.right_ring,
+            EquipSlot::Amulet => &mut self.amulet,
+        }
+    }
+
+    // put `item` in `slot` without checking it fits there, returning the item that was there before
+    fn set_slot(&mut self, slot: EquipSlot, item: Option<Item>) -> Option<Item> {
+        let previous = std::mem::replace(self.slot_mut(slot), item);
+        self.recalculate();
+        previous
+    }
+
+    pub fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    pub fn inventory_mut(&mut self) -> &mut Inventory {
+        &mut self.inventory
+    }
+
+    // whether an item of `class` can go in `slot` with what is equipped now
+    pub fn check_equip(&self, slot: EquipSlot, class: ItemClass) -> Result<(), EquipError> {
+        if !slot.accepts(class) {
+            return Err(EquipError::WrongSlot { class, slot });
+        }
+        if slot == EquipSlot::OffHand && self.main_hand.as_ref().is_some_and(|item| item.class.is_two_handed()) {
+            return Err(EquipError::OffHandBlocked);
+        }
+        Ok(())
+    }
+
+    // the slot an item of `class` goes in by default, an empty one if there is a choice, None if it can't be equipped
+    pub fn slot_for(&self, class: ItemClass) -> Option<EquipSlot> {
+        let mut slots = EquipSlot::ALL.into_iter().filter(|slot| slot.accepts(class));
+        let first = slots.clone().next()?;
+        Some(slots.find(|slot| self.slot(*slot).is_none()).unwrap_or(first))
+    }
+
+    // move the inventory item at `index` into `slot`, the items it replaces go into the inventory,
+    // a two-handed weapon replaces the off hand as well
+    pub fn equip(&mut self, index: usize, slot: EquipSlot) -> Result<(), EquipError> {
+        let item = self.inventory.get(index).ok_or(EquipError::NotInInventory(index))?;
+        self.check_equip(slot, item.class)?;
+
+        let mut replaced = vec![slot];
+        if slot == EquipSlot::MainHand && item.class.is_two_handed() {
+            replaced.push(EquipSlot::OffHand);
+        }
+        replaced.retain(|slot| self.slot(*slot).is_some());
+        // the equipped item frees up its space
+        if replaced.len() > self.inventory.free_spaces() + 1 {
+            return Err(EquipError::InventoryFull);
+        }
+
+        let item = self.inventory.remove(index);
+        for slot in replaced {
+            let previous = self.slot_mut(slot).take().expect("replaced slots aren't empty");
+            self.inventory.add(previous).expect("room was checked before equipping");
+        }
+        self.set_slot(slot, Some(item));
+        Ok(())
+    }
+
+    // move the item in `slot` into the inventory
+    pub fn unequip(&mut self, slot: EquipSlot) -> Result<(), EquipError> {
+        if self.slot(slot).is_none() {
+            return Err(EquipError::EmptySlot(slot));
+        }
+        if self.inventory.free_spaces() == 0 {
+            return Err(EquipError::InventoryFull);
+        }
+
+        let item = self.set_slot(slot, None).expect("slot was checked");
+        self.inventory.add(item).expect("room was checked before unequipping");
+        Ok(())
+    }
+
+    // modify the item in a slot, stats are recalculated afterwards
+    pub fn edit_item<R>(&mut self, slot: EquipSlot, f: impl FnOnce(&mut Item) -> R) -> Option<R> {
+        let result = self.slot_mut(slot).as_mut().map(f);
+        self.recalculate();
+        result
+    }
+
+    // the stats and modifiers each source grants, in the order they are applied
+    fn sources(&self) -> Vec<(StatSource, StatMap, Modifiers)> {
+        let mut sources = Vec::new();
+
+        let mut base = StatMap::new();
+        base.add_map(&self.base_stats);
+        sources.push((StatSource::Base, base, Modifiers::default()));
+
+        for slot in EquipSlot::ALL {
+            if let Some(item) = self.slot(slot) {
+                let mut stats = StatMap::new();
+                item.add_stats(&mut stats);
+                sources.push((StatSource::Equipment(slot), stats, Modifiers::default()));
+            }
+        }
+
+        let mut stats = self.passive_tree.stats();
+        let mut modifiers = Modifiers::default();
+        for modifier in self.passive_tree.mods() {
+            modifiers.add(&mut stats, modifier);
+        }
+        for conditional in self.passive_tree.conditional() {
+            modifiers.add_conditional(conditional.clone());
+        }
+        sources.push((StatSource::Passives, stats, modifiers));
+
+        for buff in &self.buffs {
+            let mut stats = StatMap::new();
+            let mut modifiers = Modifiers::default();
+            buff.add_stats(&mut stats);
+            for &modifier in &buff.mods {
+                modifiers.add(&mut stats, modifier);
+            }
+            for conditional in &buff.conditional {
+                modifiers.add_conditional(conditional.clone());
+            }
+            sources.push((StatSource::Buff(buff.name.clone()), stats, modifiers));
+        }
+
+        for skill in self.skills.active() {
+            let mut stats = StatMap::new();
+            skill.add_stats(&mut stats);
+            sources.push((StatSource::Skill(skill.name.clone()), stats, Modifiers::default()));
+        }
+
+        sources
+    }
+
+    pub fn modifiers(&self) -> &Modifiers {
+        &self.modifiers
+    }
+
+    pub fn recalculate(&mut self) {
         let mut stats = StatMap::new();
-        for stat in ALL_STATS {
-            stats.add(*stat, 0);
+        let mut modifiers = Modifiers::default();
+        for (_, source, source_modifiers) in self.sources() {
+            stats.add_map(&source);
+            modifiers.add_modifiers(&source_modifiers);
         }
-        Self {
-            health: 100,
-            mana: 100,
-            energy_shield: 100,
-            stats,
-            main_hand: Item::default(),
-            off_hand: Item::default(),
-            helmet: Item::default(),
-            body_armour: Item::default(),
-            gloves: Item::default(),
-            boots: Item::default(),
-            left_ring: Item::default(),
-            right_ring: Item::default(),
-            amulet: Item::default(),
-        }
-    }
-
-    pub fn default_attack_test(&mut self, rand: &mut Rand) -> String {
-        // temp weapon values
-        let wep_flat_physical_local_min = 15;
-        let wep_flat_physical_local_max = 24;
-        let wep_crit_chance_local = 500; // 5.00% chance to crit
-
-        let accuracy = self.stats.get(Accuracy);
-        let accuracy_inc = self.stats.get(AccuracyInc);
-
-        let accuracy_final = accuracy * (100 + accuracy_inc) / 100;
-
-        // compare accuracy to enemy evasion
-        // for now just assume 100% hit chance
-        if accuracy_final < 0 {
-            // attack missed
-            return String::new();
-        }
-
-        let crit_chance = wep_crit_chance_local + self.stats.get(CritChance);
-        let crit_chance_inc = self.stats.get(CritChanceInc);
-        let crit_chance_final = crit_chance * (100 + crit_chance_inc) / 100;
-
-        // for rolling crit, generate random number between 0 and CRIT_CHANCE_MAX_VALUE
-        let crit_roll = rand.gen_range(0..=CRIT_CHANCE_MAX_VALUE);
-        
-        let crit_damage_bonus_if_crit = 50 + self.stats.get(CritDamageBonus);
-        let crit_damage_bonus = if crit_roll < crit_chance_final {
-            // player has inherit 50% crit damage bonus
-            crit_damage_bonus_if_crit
-        } else {
-            // crit miss
-            0
-        };
+        self.stats = stats;
+        self.modifiers = modifiers;
 
-        let increased_dmg =
-            self.stats.get(DamageInc) +
-            self.stats.get(PhysicalInc) +
-            self.stats.get(AttackInc);
-
-        let wep_flat_physical_local_min_final = wep_flat_physical_local_min * (100 + increased_dmg) / 100;
-        let wep_flat_physical_local_max_final = wep_flat_physical_local_max * (100 + increased_dmg) / 100;
-        
-        // use rand to generate a random number between wep_flat_physical_local_min_final and wep_flat_physical_local_max_final
-        let weapon_physical_damage_final = rand.gen_range(wep_flat_physical_local_min_final..=wep_flat_physical_local_max_final);
-
-        let weapon_critical_physical_damage_final = weapon_physical_damage_final * (100 + crit_damage_bonus) / 100;
-
-        let wep_critical_physical_local_min_final = wep_flat_physical_local_min_final * (100 + crit_damage_bonus_if_crit) / 100;
-        let wep_critical_physical_local_max_final = wep_flat_physical_local_max_final * (100 + crit_damage_bonus_if_crit) / 100;
-
-        format!("Weapon deals {} to {} physical damage with a base critical hit chance of {:.2}.
-Base Critical Hit Chance is {} after player modifiers, and is Increased by {}% to get a final critical hit chance of {:.2}.
-If the hit is a Critical Hit, the Default attack's damage will be increased by {}% Critical Damage Bonus. 
-Default attack will deal {} to {} physical damage with Increased Damage modifiers of {}% Damage, {}% Attack Damage, and {}% Physical Damage.
-If the hit is a Critical Hit, Default attack will deal {} to {} physical damage.
-
-With random rolls, the default attack did {} physical damage, with a {}.", 
-            wep_flat_physical_local_min, wep_flat_physical_local_max, wep_crit_chance_local as f32 / 100.0,
-            crit_chance as f32 / 100.0, self.stats.get(CritChanceInc), crit_chance_final as f32 / 100.0,
-            crit_damage_bonus_if_crit,
-            wep_flat_physical_local_min_final, wep_flat_physical_local_max_final, self.stats.get(DamageInc), self.stats.get(PhysicalInc), self.stats.get(AttackInc),
-            wep_critical_physical_local_min_final, wep_critical_physical_local_max_final,
-            weapon_critical_physical_damage_final, if crit_roll < crit_chance_final {"Critical Hit"} else {"Non-Critical Hit"},
-        )
+        // skills that reserve more spirit than is left are turned off, which can change the stats again
+        if self.skills.release(stat_value(self, MaxSpirit)) {
+            self.recalculate();
+            return;
+        }
+
+        // current pools can't be above the new maximums
+        let max = self.max_pools();
+        self.pools.health = self.pools.health.min(max.health);
+        self.pools.mana = self.pools.mana.min(max.mana);
+        self.pools.energy_shield = self.pools.energy_shield.min(max.energy_shield);
+    }
+
+    // how much of `stat` each source contributed, sources that contributed nothing are skipped
+    pub fn contributions(&self, stat: Stat) -> Vec<(StatSource, i32)> {
+        self.sources()
+            .into_iter()
+            .map(|(source, stats, _)| (source, stats.get(stat)))
+            .filter(|(_, value)| *value != 0)
+            .collect()
+    }
+
+    pub fn max_pools(&self) -> Pools {
+        max_pools(&self.stats, &self.modifiers)
+    }
+
+    pub fn restore_pools(&mut self) {
+        self.pools = self.max_pools();
+    }
+
+    pub fn main_hand_weapon(&self) -> WeaponBase {
+        self.main_hand.as_ref()
+            .and_then(Item::local_weapon)
+            .unwrap_or_else(WeaponBase::unarmed)
+    }
+
+    pub fn main_hand_name(&self) -> &str {
+        match &self.main_hand {
+            Some(item) if item.weapon.is_some() => &item.name,
+            _ => "Unarmed",
+        }
     }
-}
 
-pub struct PassiveTree {
+    // the skill in the first slot is used for plain attacks and simulations, as long as it hits
+    pub fn main_skill(&self) -> Option<&Skill> {
+        self.skills.get(0).map(|slot| &slot.skill).filter(|skill| !skill.is_persistent())
+    }
 
+    pub fn default_attack_test(&self, rand: &mut Rand) -> HitReport {
+        roll_attack(self, rand)
+    }
 }
 
-#[derive(Default, serde::Deserialize, serde::Serialize)]
-pub struct Item {
+impl Default for Player {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Combatant for Player {
+    fn stats(&self) -> &StatMap {
+        &self.stats
+    }
+
+    fn modifiers(&self) -> &Modifiers {
+        &self.modifiers
+    }
+
+    fn weapon(&self) -> WeaponBase {
+        self.main_hand_weapon()
+    }
+
+    fn hit_source(&self) -> HitSource {
+        let weapon = self.main_hand_weapon();
+        self.main_skill()
+            .and_then(|skill| skill.hit_source(&weapon))
+            .unwrap_or_else(|| HitSource::weapon(&weapon))
+    }
+
+    fn pools(&self) -> &Pools {
+        &self.pools
+    }
+
+    fn pools_mut(&mut self) -> &mut Pools {
+        &mut self.pools
+    }
+
+    fn ailments(&self) -> &Ailments {
+        &self.ailments
+    }
 
+    fn ailments_mut(&mut self) -> &mut Ailments {
+        &mut self.ailments
+    }
+
+    fn debuffs(&self) -> &Debuffs {
+        &self.debuffs
+    }
+
+    fn debuffs_mut(&mut self) -> &mut Debuffs {
+        &mut self.debuffs
+    }
 }
 
-pub struct Modifier {
-    kind: Stat,
-    value: Range<i32>,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attack_uses_main_hand() {
+        use damage::{DamageRange, DamageType};
+        use rand::SeedableRng;
+
+        let mut weapon = WeaponBase::physical(40, 60, 1400, 650);
+        weapon.damage[DamageType::Cold] = DamageRange::new(5, 10);
+        let mut player = Player::new();
+        player.set_slot(EquipSlot::MainHand, Some(Item::new_weapon("Test Maul", ItemClass::Mace, 1, weapon.clone())));
+
+        let mut rand = Rand::seed_from_u64(0);
+        let report = player.default_attack_test(&mut rand);
+        assert_eq!(report.base_damage, weapon.damage);
+        assert_eq!(report.base_crit_chance, weapon.crit_chance);
+        assert!((40..=60).contains(&report.damage[DamageType::Physical]));
+        assert!((5..=10).contains(&report.damage[DamageType::Cold]));
+        assert_eq!(player.hit_source().base_time, weapon.attack_time);
+    }
+
+    #[test]
+    fn test_hits_evasive_monster() {
+        use rand::SeedableRng;
+
+        let player = Player::new();
+        let bestiary = monster::Bestiary::builtin();
+        let archer = bestiary.get("Skeleton Archer").unwrap();
+        let mut rand = Rand::seed_from_u64(0);
+        let hits = (0..1000)
+            .filter(|_| combat::resolve_hit(&player, &mut archer.spawn(1, item::Rarity::Normal), &mut rand).hit)
+            .count();
+        assert!(hits > 900, "{hits} of 1000 attacks hit");
+    }
+
+    #[test]
+    fn test_contributions() {
+        let mut player = Player::new();
+        player.set_base_stat(DamageInc, 10);
+        player.edit_item(EquipSlot::MainHand, |item| {
+            item.affixes.push(affix::RolledAffix {
+                name: "of Testing".to_owned(),
+                group: "DamageInc".to_owned(),
+                kind: affix::AffixKind::Suffix,
+                modifiers: vec![(DamageInc, 15), (PhysicalLocalInc, 50)],
+            });
+        });
+        player.add_buff(Buff::new("Rage", vec![(DamageInc, 25)], Some(1000)));
+
+        assert_eq!(player.stats().get(DamageInc), 50);
+        assert_eq!(player.stats().get(PhysicalLocalInc), 0);
+        assert_eq!(
+            player.contributions(DamageInc),
+            vec![
+                (StatSource::Base, 10),
+                (StatSource::Equipment(EquipSlot::MainHand), 15),
+                (StatSource::Buff("Rage".to_owned()), 25),
+            ],
+        );
+
+        player.tick_buffs(1000);
+        assert!(player.buffs().is_empty());
+        assert_eq!(player.stats().get(DamageInc), 25);
+    }
+
+    #[test]
+    fn test_pools_follow_max() {
+        let mut player = Player::new();
+        assert_eq!(player.pools().health, 100);
+
+        player.set_base_stat(MaxHealthInc, -50);
+        assert_eq!(player.max_pools().health, 50);
+        assert_eq!(player.pools().health, 50);
+    }
+
+    #[test]
+    fn test_save_round_trip() {
+        let mut player = Player::new();
+        player.set_base_stat(DamageInc, 10);
+        player.allocate_passives(&[11, 10]).unwrap();
+        player.unequip(EquipSlot::MainHand).unwrap();
+        player.pools_mut().health = 1;
+
+        let saved: Player = ron::from_str(&ron::to_string(&player).unwrap()).unwrap();
+        assert_eq!(saved.base_stat(DamageInc), 10);
+        assert!(saved.passive_tree().is_allocated(11));
+        assert!(saved.slot(EquipSlot::MainHand).is_none());
+        assert_eq!(saved.inventory().items()[0].name, "Short Sword");
+        assert_eq!(saved.pools().health, 1);
+        for &stat in ALL_STATS {
+            assert_eq!(saved.stats().get(stat), player.stats().get(stat));
+        }
+
+        // stats aren't saved, a save whose nodes no longer connect loads with them refunded
+        let text = ron::to_string(&player).unwrap().replace("allocated:[10,11]", "allocated:[11]");
+        let saved: Player = ron::from_str(&text).unwrap();
+        assert!(!saved.passive_tree().is_allocated(11));
+        assert_eq!(saved.stats().get(Accuracy), Player::new().stats().get(Accuracy));
+        assert_eq!(saved.stats().get(AttackSpeed), 0);
+    }
+
+    #[test]
+    fn test_equip_slots() {
+        let mut player = Player::new();
+        let mut ring = Item::new("Iron Ring", ItemClass::Ring, 1);
+        ring.affixes.push(affix::RolledAffix {
+            name: "Hale".to_owned(),
+            group: "MaxHealth".to_owned(),
+            kind: affix::AffixKind::Prefix,
+            modifiers: vec![(MaxHealth, 20)],
+        });
+        let inventory = player.inventory_mut();
+        inventory.add(Item::new("Buckler", ItemClass::Shield, 1)).unwrap();
+        inventory.add(Item::new_weapon("Long Bow", ItemClass::Bow, 1, WeaponBase::physical(10, 30, 1200, 600))).unwrap();
+        inventory.add(ring).unwrap();
+        inventory.add(Item::new("Gold Ring", ItemClass::Ring, 1)).unwrap();
+
+        assert_eq!(
+            player.equip(0, EquipSlot::Helmet),
+            Err(EquipError::WrongSlot { class: ItemClass::Shield, slot: EquipSlot::Helmet }),
+        );
+        player.equip(0, EquipSlot::OffHand).unwrap();
+
+        // the bow takes both hands, the sword and the shield go back into the inventory
+        player.equip(0, EquipSlot::MainHand).unwrap();
+        assert_eq!(player.main_hand_weapon(), WeaponBase::physical(10, 30, 1200, 600));
+        assert!(player.slot(EquipSlot::OffHand).is_none());
+        let names = player.inventory().items().iter().map(|item| item.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["Iron Ring", "Gold Ring", "Short Sword", "Buckler"]);
+        assert_eq!(player.equip(3, EquipSlot::OffHand), Err(EquipError::OffHandBlocked));
+
+        // rings fill whichever ring slot is empty
+        for _ in 0..2 {
+            let slot = player.slot_for(ItemClass::Ring).unwrap();
+            player.equip(0, slot).unwrap();
+        }
+        assert_eq!(player.slot(EquipSlot::LeftRing).unwrap().name, "Iron Ring");
+        assert_eq!(player.slot(EquipSlot::RightRing).unwrap().name, "Gold Ring");
+        assert_eq!(player.max_pools().health, 120);
+        assert_eq!(player.slot_for(ItemClass::Currency), None);
+
+        player.unequip(EquipSlot::LeftRing).unwrap();
+        assert_eq!(player.max_pools().health, 100);
+        assert_eq!(player.unequip(EquipSlot::LeftRing), Err(EquipError::EmptySlot(EquipSlot::LeftRing)));
+        assert_eq!(player.inventory().len(), 3);
+    }
+
+    #[test]
+    fn test_take_partial_stack() {
+        let mut game = Game::new(0);
+        let inventory = game.player.inventory_mut();
+        inventory.add(Item::new_currency("Chaos Orb", CURRENCY_STACK_SIZE - 1)).unwrap();
+        while inventory.free_spaces() > 0 {
+            inventory.add(Item::new("Iron Ring", ItemClass::Ring, 1)).unwrap();
+        }
+
+        // the graveyard has two orbs and only one fits
+        game.run("n");
+        game.run("e");
+        assert_eq!(game.run("take orb")[0].text(), "You pick up the Chaos Orb (no room for 1).");
+        assert_eq!(game.player.inventory().items()[0].stack, CURRENCY_STACK_SIZE);
+        assert_eq!(game.room().items.iter().find(|item| item.name == "Chaos Orb").unwrap().stack, 1);
+        assert_eq!(game.run("take orb")[0].text(), "There is no room in your pack for the Chaos Orb.");
+    }
+
+    #[test]
+    fn test_unequip_into_full_pack() {
+        let mut game = Game::new(0);
+        let inventory = game.player.inventory_mut();
+        while inventory.free_spaces() > 0 {
+            inventory.add(Item::new("Iron Ring", ItemClass::Ring, 1)).unwrap();
+        }
+
+        assert_eq!(game.run("unequip sword")[0].text(), "There is no room in your pack.");
+        assert!(game.player.slot(EquipSlot::MainHand).is_some());
+    }
+
+    #[test]
+    fn test_adventure() {
+        let mut game = Game::new(0);
+        assert_eq!(game.run("look")[0].text(), "Camp - Outskirts, area level 1");
+        assert_eq!(game.run("west"), vec!["You can't go west.".into()]);
+
+        game.run("take axe");
+        assert_eq!(game.player.inventory().len(), 1);
+        game.run("equip rusty axe");
+        assert_eq!(game.player.main_hand_name(), "Rusty Axe");
+        assert_eq!(game.player.inventory().items()[0].name, "Short Sword");
+
+        game.run("n");
+        assert_eq!(game.room().name, "Old Road");
+        for _ in 0..100 {
+            if game.room().monsters.is_empty() {
+                break;
+            }
+            game.run("attack zombie");
+        }
+        assert_eq!(game.room().name, "Old Road");
+        assert!(game.room().monsters.is_empty());
+        assert_eq!(game.run("attack zombie"), vec!["There is no zombie here.".into()]);
+    }
+
+    #[test]
+    fn test_passive_mods() {
+        use damage::{DamageRange, DamageType};
+        use rand::SeedableRng;
+
+        // chaos eater converts a quarter of physical damage to chaos
+        let mut player = Player::new();
+        player.allocate_passives(&[20, 21, 22, 24]).unwrap();
+        let report = player.default_attack_test(&mut Rand::seed_from_u64(0));
+        assert_eq!(report.base_damage[DamageType::Physical], DamageRange::new(11, 18));
+        assert_eq!(report.base_damage[DamageType::Chaos], DamageRange::new(3, 6));
+
+        // reckless precision makes all damage 10% more
+        let mut player = Player::new();
+        player.allocate_passives(&[10, 11, 12, 14]).unwrap();
+        let report = player.default_attack_test(&mut Rand::seed_from_u64(0));
+        assert_eq!(report.more[DamageType::Physical], 110);
+    }
 }
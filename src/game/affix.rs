@@ -0,0 +1,180 @@
+use std::ops::Range;
+
+use crate::Rand;
+use rand::prelude::*;
+
+use super::item::*;
+use super::stats::Stat;
+
+const BUILTIN_AFFIXES: &str = include_str!("../../data/affixes.ron");
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Modifier {
+    pub kind: Stat,
+    pub value: Range<i32>,
+}
+
+impl Modifier {
+    pub fn roll(&self, rand: &mut Rand) -> i32 {
+        if self.value.is_empty() {
+            self.value.start
+        } else {
+            rand.gen_range(self.value.clone())
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum AffixKind {
+    Prefix,
+    Suffix,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct AffixDef {
+    pub name: String,
+    pub kind: AffixKind,
+    pub group: String, // only one affix of a group can roll on an item, so tiers of the same modifier don't stack
+    pub level: i32,    // minimum item level
+    pub weight: u32,
+    pub classes: Vec<ItemClass>,
+    pub modifiers: Vec<Modifier>,
+}
+
+impl AffixDef {
+    fn roll(&self, rand: &mut Rand) -> RolledAffix {
+        RolledAffix {
+            name: self.name.clone(),
+            group: self.group.clone(),
+            kind: self.kind,
+            modifiers: self.modifiers.iter().map(|m| (m.kind, m.roll(rand))).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct RolledAffix {
+    pub name: String,
+    pub group: String,
+    pub kind: AffixKind,
+    pub modifiers: Vec<(Stat, i32)>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct AffixPool {
+    pub affixes: Vec<AffixDef>,
+}
+
+impl AffixPool {
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+
+    pub fn builtin() -> Self {
+        Self::from_ron(BUILTIN_AFFIXES).expect("builtin affixes should be valid")
+    }
+
+    // affixes of `kind` that can roll on `item` without clashing with the affixes it already has
+    pub fn candidates<'a>(&'a self, item: &'a Item, kind: AffixKind) -> impl Iterator<Item = &'a AffixDef> {
+        self.affixes.iter().filter(move |affix| {
+            affix.kind == kind
+                && affix.weight > 0
+                && affix.level <= item.level
+                && affix.classes.contains(&item.class)
+                && !item.affixes.iter().any(|rolled| rolled.group == affix.group)
+        })
+    }
+
+    // pick one affix of `kind` by weight and add it to the item, returns false if nothing could roll
+    pub fn roll_affix(&self, item: &mut Item, kind: AffixKind, rand: &mut Rand) -> bool {
+        let total: u32 = self.candidates(item, kind).map(|affix| affix.weight).sum();
+        if total == 0 {
+            return false;
+        }
+
+        let mut roll = rand.gen_range(0..total);
+        let affix = self.candidates(item, kind)
+            .find(|affix| {
+                if roll < affix.weight {
+                    true
+                } else {
+                    roll -= affix.weight;
+                    false
+                }
+            })
+            .expect("roll is always less than the total weight");
+
+        let rolled = affix.roll(rand);
+        item.affixes.push(rolled);
+        true
+    }
+
+    // replace the item's affixes with a new set of affixes for `rarity`
+    pub fn roll_item(&self, item: &mut Item, rarity: Rarity, rand: &mut Rand) {
+        item.rarity = rarity;
+        item.affixes.clear();
+
+        let max = rarity.max_affixes();
+        if max == 0 {
+            return;
+        }
+
+        // magic items have 1 or 2 affixes, rare items have 3 to 6
+        let min = if rarity == Rarity::Rare { 3 } else { 1 };
+        let count = rand.gen_range(min..=max * 2);
+
+        let mut open = vec![AffixKind::Prefix, AffixKind::Suffix];
+        while item.affixes.len() < count && !open.is_empty() {
+            let index = rand.gen_range(0..open.len());
+            let kind = open[index];
+
+            if item.affix_count(kind) >= max || !self.roll_affix(item, kind, rand) {
+                open.swap_remove(index);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roll_rare_item() {
+        let mut rand = Rand::seed_from_u64(0);
+        let pool = AffixPool::builtin();
+
+        for _ in 0..100 {
+            let mut item = Item::new("Iron Ring", ItemClass::Ring, 10);
+            pool.roll_item(&mut item, Rarity::Rare, &mut rand);
+
+            assert!(item.affix_count(AffixKind::Prefix) <= 3);
+            assert!(item.affix_count(AffixKind::Suffix) <= 3);
+            assert!(!item.affixes.is_empty());
+
+            for (i, rolled) in item.affixes.iter().enumerate() {
+                let def = pool.affixes.iter().find(|def| def.name == rolled.name).unwrap();
+                assert!(def.classes.contains(&ItemClass::Ring));
+                assert!(def.level <= 10);
+                assert!(item.affixes[i + 1..].iter().all(|other| other.group != rolled.group));
+
+                for (modifier, (stat, value)) in def.modifiers.iter().zip(&rolled.modifiers) {
+                    assert_eq!(modifier.kind, *stat);
+                    assert!(modifier.value.contains(value));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_item_level_limits_affixes() {
+        let mut rand = Rand::seed_from_u64(0);
+        let pool = AffixPool::builtin();
+        let mut item = Item::new("Rusted Sword", ItemClass::Sword, 1);
+
+        for _ in 0..100 {
+            pool.roll_item(&mut item, Rarity::Magic, &mut rand);
+            assert!(item.affixes.iter().all(|affix| affix.name != "Cruel"));
+        }
+    }
+}
@@ -0,0 +1,347 @@
+use crate::Rand;
+use rand::prelude::*;
+
+use super::combat::*;
+use super::damage::*;
+use super::modifier::Modifiers;
+use super::stats::*;
+use super::CRIT_CHANCE_MAX_VALUE;
+
+// shock and chill effect grows with the share of the target's maximum health dealt by the hit
+const SHOCK_EFFECT_MAX: i32 = 50;
+const CHILL_EFFECT_MAX: i32 = 30;
+const EFFECT_MIN: i32 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum AilmentKind {
+    Bleed,
+    Ignite,
+    Poison,
+    Shock,
+    Chill,
+    Freeze,
+}
+
+impl AilmentKind {
+    pub const ALL: [AilmentKind; 6] = [
+        AilmentKind::Bleed,
+        AilmentKind::Ignite,
+        AilmentKind::Poison,
+        AilmentKind::Shock,
+        AilmentKind::Chill,
+        AilmentKind::Freeze,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AilmentKind::Bleed => "Bleed",
+            AilmentKind::Ignite => "Ignite",
+            AilmentKind::Poison => "Poison",
+            AilmentKind::Shock => "Shock",
+            AilmentKind::Chill => "Chill",
+            AilmentKind::Freeze => "Freeze",
+        }
+    }
+
+    pub fn chance_stat(self) -> Stat {
+        match self {
+            AilmentKind::Bleed => BleedChance,
+            AilmentKind::Ignite => IgniteChance,
+            AilmentKind::Poison => PoisonChance,
+            AilmentKind::Shock => ShockChance,
+            AilmentKind::Chill => ChillChance,
+            AilmentKind::Freeze => FreezeChance,
+        }
+    }
+
+    // increases damage for damaging ailments, effect for shock and chill and duration for freeze
+    pub fn inc_stat(self) -> Stat {
+        match self {
+            AilmentKind::Bleed => BleedInc,
+            AilmentKind::Ignite => IgniteInc,
+            AilmentKind::Poison => PoisonInc,
+            AilmentKind::Shock => ShockInc,
+            AilmentKind::Chill => ChillInc,
+            AilmentKind::Freeze => FreezeInc,
+        }
+    }
+
+    // the damage types of a hit that can inflict the ailment, and that its magnitude is based on
+    pub fn inflicted_by(self) -> &'static [DamageType] {
+        match self {
+            AilmentKind::Bleed => &[DamageType::Physical],
+            AilmentKind::Ignite => &[DamageType::Fire],
+            AilmentKind::Poison => &[DamageType::Physical, DamageType::Chaos],
+            AilmentKind::Shock => &[DamageType::Lightning],
+            AilmentKind::Chill | AilmentKind::Freeze => &[DamageType::Cold],
+        }
+    }
+
+    // the type of damage over time dealt, None for ailments that don't deal damage
+    pub fn damage_type(self) -> Option<DamageType> {
+        match self {
+            AilmentKind::Bleed => Some(DamageType::Physical),
+            AilmentKind::Ignite => Some(DamageType::Fire),
+            AilmentKind::Poison => Some(DamageType::Chaos),
+            _ => None,
+        }
+    }
+
+    // in milliseconds, before ExpireSpeed
+    pub fn base_duration(self) -> i32 {
+        match self {
+            AilmentKind::Bleed => 5000,
+            AilmentKind::Ignite => 4000,
+            AilmentKind::Poison => 2000,
+            AilmentKind::Shock => 4000,
+            AilmentKind::Chill => 2000,
+            AilmentKind::Freeze => 1000,
+        }
+    }
+
+    // damage per second as a percent of the hit's damage, for damaging ailments
+    fn damage_percent(self) -> i32 {
+        match self {
+            AilmentKind::Bleed => 70,
+            AilmentKind::Ignite => 90,
+            AilmentKind::Poison => 30,
+            _ => 0,
+        }
+    }
+
+    // every poison deals damage, only the strongest of the other ailments applies
+    pub fn stacks(self) -> bool {
+        self == AilmentKind::Poison
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Ailment {
+    pub kind: AilmentKind,
+    pub magnitude: i32, // damage per second for damaging ailments, effect in percent for shock and chill
+    pub duration: i32,  // in milliseconds
+    pub elapsed: i32,
+}
+
+impl Ailment {
+    pub fn remaining(&self) -> i32 {
+        self.duration - self.elapsed
+    }
+
+    // damage dealt between `from` and `to` milliseconds, computed from the total so rounding doesn't lose damage
+    fn damage_between(&self, from: i32, to: i32) -> i32 {
+        let dealt = |elapsed: i32| (self.magnitude as i64 * elapsed.min(self.duration) as i64 / 1000) as i32;
+        dealt(to) - dealt(from)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Ailments {
+    active: Vec<Ailment>,
+}
+
+impl Ailments {
+    pub fn iter(&self) -> impl Iterator<Item = &Ailment> {
+        self.active.iter()
+    }
+
+    pub fn apply(&mut self, ailment: Ailment) {
+        self.active.push(ailment);
+    }
+
+    pub fn clear(&mut self) {
+        self.active.clear();
+    }
+
+    // the strongest active ailment of `kind`
+    pub fn strongest(&self, kind: AilmentKind) -> Option<&Ailment> {
+        self.active.iter().filter(|ailment| ailment.kind == kind).max_by_key(|ailment| ailment.magnitude)
+    }
+
+    // percent increased damage taken
+    pub fn shock(&self) -> i32 {
+        self.strongest(AilmentKind::Shock).map_or(0, |ailment| ailment.magnitude)
+    }
+
+    // percent less action speed
+    pub fn chill(&self) -> i32 {
+        self.strongest(AilmentKind::Chill).map_or(0, |ailment| ailment.magnitude)
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.strongest(AilmentKind::Freeze).is_some()
+    }
+
+    // advance every ailment by `elapsed` milliseconds, returning the damage over time dealt before mitigation
+    pub fn tick(&mut self, elapsed: i32) -> DamageMap<i32> {
+        let mut damage = DamageMap::default();
+
+        for kind in AilmentKind::ALL {
+            let Some(damage_type) = kind.damage_type() else {
+                continue;
+            };
+
+            if kind.stacks() {
+                for ailment in self.active.iter().filter(|ailment| ailment.kind == kind) {
+                    damage[damage_type] += ailment.damage_between(ailment.elapsed, ailment.elapsed + elapsed);
+                }
+            } else if let Some(ailment) = self.strongest(kind) {
+                damage[damage_type] += ailment.damage_between(ailment.elapsed, ailment.elapsed + elapsed);
+            }
+        }
+
+        for ailment in &mut self.active {
+            ailment.elapsed += elapsed;
+        }
+        self.active.retain(|ailment| ailment.remaining() > 0);
+
+        damage
+    }
+}
+
+// roll each ailment the hit can inflict, using the damage the attacker rolled
+pub fn roll_ailments(
+    attacker: &StatMap,
+    modifiers: &Modifiers,
+    defender: &impl Combatant,
+    damage: &DamageMap<i32>,
+    rand: &mut Rand,
+) -> Vec<Ailment> {
+    let mut ailments = Vec::new();
+
+    for kind in AilmentKind::ALL {
+        let chance = modifiers.value(attacker, kind.chance_stat(), 0);
+        let hit_damage: i32 = kind.inflicted_by().iter().map(|&damage_type| damage[damage_type]).sum();
+        if chance <= 0 || hit_damage <= 0 {
+            continue;
+        }
+
+        if rand.gen_range(0..CRIT_CHANCE_MAX_VALUE) >= chance {
+            continue;
+        }
+
+        let increase = modifiers.percent(attacker, &[kind.inc_stat()]);
+        let mut duration = kind.base_duration();
+        let magnitude = match kind {
+            AilmentKind::Bleed | AilmentKind::Ignite | AilmentKind::Poison => {
+                let percent = modifiers.percent(attacker, &[DamageOverTimeInc, kind.inc_stat()]);
+                hit_damage * kind.damage_percent() / 100 * percent / 100
+            }
+            AilmentKind::Shock | AilmentKind::Chill => {
+                let max = if kind == AilmentKind::Shock { SHOCK_EFFECT_MAX } else { CHILL_EFFECT_MAX };
+                let max_health = max_pools(defender.stats(), defender.modifiers()).health.max(1);
+                let effect = hit_damage * 100 / max_health / 2 * increase / 100;
+                effect.clamp(EFFECT_MIN, max)
+            }
+            AilmentKind::Freeze => {
+                duration = duration * increase / 100;
+                0
+            }
+        };
+
+        // the target's expire speed makes ailments on it run out faster
+        let duration = duration * 100 / (100 + defender.stats().get(ExpireSpeed)).max(1);
+
+        ailments.push(Ailment {
+            kind,
+            magnitude,
+            duration,
+            elapsed: 0,
+        });
+    }
+
+    ailments
+}
+
+// advance the defender's ailments and apply their damage over time after resistances and shock
+pub fn tick_ailments(defender: &mut impl Combatant, elapsed: i32) -> DamageMap<i32> {
+    let damage = defender.ailments_mut().tick(elapsed);
+    let shock = defender.ailments().shock();
+
+    let mut taken = DamageMap::default();
+    for (kind, &amount) in damage.iter() {
+        // armour only applies to hits, so physical damage over time isn't reduced
+        let reduction = resist(defender.stats(), kind);
+        taken[kind] = (amount * (100 - reduction) / 100 * (100 + shock) / 100).max(0);
+    }
+
+    take_damage(defender.pools_mut(), &taken);
+    taken
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Player;
+
+    fn ailment(kind: AilmentKind, magnitude: i32, duration: i32) -> Ailment {
+        Ailment {
+            kind,
+            magnitude,
+            duration,
+            elapsed: 0,
+        }
+    }
+
+    #[test]
+    fn test_stacking() {
+        let mut ailments = Ailments::default();
+        ailments.apply(ailment(AilmentKind::Poison, 10, 2000));
+        ailments.apply(ailment(AilmentKind::Poison, 20, 2000));
+        ailments.apply(ailment(AilmentKind::Ignite, 100, 1000));
+        ailments.apply(ailment(AilmentKind::Ignite, 50, 3000));
+
+        let damage = ailments.tick(1000);
+        assert_eq!(damage[DamageType::Chaos], 30);
+        assert_eq!(damage[DamageType::Fire], 100);
+
+        // the strongest ignite expired, the weaker one takes over
+        let damage = ailments.tick(1000);
+        assert_eq!(damage[DamageType::Chaos], 30);
+        assert_eq!(damage[DamageType::Fire], 50);
+
+        ailments.tick(1000);
+        assert_eq!(ailments.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_small_ticks_lose_no_damage() {
+        let mut ailments = Ailments::default();
+        ailments.apply(ailment(AilmentKind::Bleed, 7, 1000));
+
+        let total: i32 = (0..100).map(|_| ailments.tick(10)[DamageType::Physical]).sum();
+        assert_eq!(total, 7);
+    }
+
+    #[test]
+    fn test_roll_ailments() {
+        let mut rand = Rand::seed_from_u64(0);
+        let mut attacker = StatMap::new();
+        attacker.add(IgniteChance, CRIT_CHANCE_MAX_VALUE);
+        attacker.add(ShockChance, CRIT_CHANCE_MAX_VALUE);
+        attacker.add(DamageOverTimeInc, 50);
+
+        let mut defender = Player::new();
+        defender.set_base_stat(MaxHealth, 1000);
+        defender.set_base_stat(ExpireSpeed, 100);
+
+        let mut damage = DamageMap::default();
+        damage[DamageType::Fire] = 100;
+        damage[DamageType::Lightning] = 200;
+
+        let ailments = roll_ailments(&attacker, &Modifiers::default(), &defender, &damage, &mut rand);
+        assert_eq!(
+            ailments,
+            vec![
+                ailment(AilmentKind::Ignite, 135, 2000),
+                ailment(AilmentKind::Shock, 10, 2000),
+            ],
+        );
+
+        // more damage over time multiplies the increases
+        let mut modifiers = Modifiers::default();
+        modifiers.add(&mut attacker, crate::game::modifier::Mod::More(DamageOverTimeInc, 20));
+        let ailments = roll_ailments(&attacker, &modifiers, &defender, &damage, &mut rand);
+        assert_eq!(ailments[0], ailment(AilmentKind::Ignite, 162, 2000));
+    }
+}
@@ -0,0 +1,36 @@
+use super::modifier::{ConditionalMod, Mod};
+use super::stats::*;
+
+// a temporary effect that grants stats until it expires
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Buff {
+    pub name: String,
+    pub stats: Vec<(Stat, i32)>,
+    #[serde(default)]
+    pub mods: Vec<Mod>,
+    #[serde(default)]
+    pub conditional: Vec<ConditionalMod>,
+    pub remaining: Option<i32>, // in milliseconds, None lasts until removed
+}
+
+impl Buff {
+    pub fn new(name: &str, stats: Vec<(Stat, i32)>, duration: Option<i32>) -> Self {
+        Self {
+            name: name.to_owned(),
+            stats,
+            mods: Vec::new(),
+            conditional: Vec::new(),
+            remaining: duration,
+        }
+    }
+
+    pub fn add_stats(&self, stats: &mut StatMap) {
+        for &(stat, value) in &self.stats {
+            stats.add(stat, value);
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining.is_some_and(|remaining| remaining <= 0)
+    }
+}
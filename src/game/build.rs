@@ -0,0 +1,128 @@
+use std::fmt;
+
+use super::buff::Buff;
+use super::inventory::EquipError;
+use super::item::*;
+use super::passive::*;
+use super::skill::*;
+use super::stats::*;
+use super::Player;
+
+// a character written down in a file, everything that isn't listed is left as it is on a new player
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct Build {
+    #[serde(default)]
+    pub base_stats: Vec<(Stat, i32)>,
+    #[serde(default)]
+    pub items: Vec<(EquipSlot, Item)>,
+    #[serde(default)]
+    pub passives: Vec<NodeId>,
+    #[serde(default)]
+    pub buffs: Vec<Buff>,
+    // names of builtin skills, put on the skill bar in order, the first one is used by the dps calculator
+    #[serde(default)]
+    pub skills: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum BuildError {
+    Parse(ron::error::SpannedError),
+    Passive(PassiveError),
+    Equip(EquipSlot, EquipError),
+    UnknownSkill(String),
+    TooManySkills(usize),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Parse(err) => write!(f, "failed to parse build: {err}"),
+            BuildError::Passive(err) => write!(f, "invalid passives: {err}"),
+            BuildError::Equip(slot, err) => write!(f, "invalid {} item: {err}", slot.name().to_lowercase()),
+            BuildError::UnknownSkill(name) => write!(f, "unknown skill {name}"),
+            BuildError::TooManySkills(count) => {
+                write!(f, "{count} skills don't fit on a skill bar with {SKILL_BAR_SLOTS} slots")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl Build {
+    pub fn from_ron(text: &str) -> Result<Self, BuildError> {
+        ron::from_str(text).map_err(BuildError::Parse)
+    }
+
+    pub fn player(&self) -> Result<Player, BuildError> {
+        let mut player = Player::new();
+
+        for &(stat, value) in &self.base_stats {
+            player.set_base_stat(stat, value);
+        }
+
+        for (slot, item) in &self.items {
+            player.check_equip(*slot, item.class).map_err(|err| BuildError::Equip(*slot, err))?;
+            player.set_slot(*slot, Some(item.clone()));
+        }
+        // items can be listed in any order, so a two-handed weapon may come after the off hand
+        if let Some(item) = player.slot(EquipSlot::OffHand) {
+            player.check_equip(EquipSlot::OffHand, item.class).map_err(|err| BuildError::Equip(EquipSlot::OffHand, err))?;
+        }
+
+        // passives can be listed in any order
+        player.allocate_passives(&self.passives).map_err(BuildError::Passive)?;
+
+        for buff in &self.buffs {
+            player.add_buff(buff.clone());
+        }
+
+        if self.skills.len() > SKILL_BAR_SLOTS {
+            return Err(BuildError::TooManySkills(self.skills.len()));
+        }
+        let book = SkillBook::builtin();
+        for (slot, name) in self.skills.iter().enumerate() {
+            let skill = book.get(name).ok_or_else(|| BuildError::UnknownSkill(name.clone()))?;
+            player.set_skill(slot, Some(skill.clone())).expect("skills were checked to fit on the bar");
+        }
+
+        player.restore_pools();
+        Ok(player)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::combat::{Combatant, HitKind};
+    use crate::game::damage::*;
+
+    #[test]
+    fn test_example_build() {
+        let build = Build::from_ron(include_str!("../../data/builds/example.ron")).unwrap();
+        let player = build.player().unwrap();
+
+        assert_eq!(player.main_hand_name(), "Bloodthirst");
+        let weapon = player.slot(EquipSlot::MainHand).unwrap().weapon.as_ref().unwrap();
+        assert_eq!(weapon.damage, DamageMap::from_pairs(&[(DamageType::Physical, DamageRange::new(18, 33))]));
+        assert_eq!(player.passive_tree().points_spent(), build.passives.len() as u32);
+    }
+
+    #[test]
+    fn test_caster_build() {
+        let build = Build::from_ron(include_str!("../../data/builds/caster.ron")).unwrap();
+        let player = build.player().unwrap();
+
+        assert_eq!(player.main_skill().unwrap().name, "Fireball");
+        assert_eq!(player.hit_source().kind(), HitKind::Spell);
+    }
+
+    #[test]
+    fn test_disconnected_passives() {
+        let build = Build {
+            passives: vec![3],
+            ..Default::default()
+        };
+        assert!(matches!(build.player(), Err(BuildError::Passive(PassiveError::NotConnected(3)))));
+    }
+}
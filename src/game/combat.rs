@@ -0,0 +1,571 @@
+use crate::Rand;
+use rand::prelude::*;
+
+use super::ailment::*;
+use super::damage::*;
+use super::debuff::*;
+use super::item::WeaponBase;
+use super::modifier::*;
+use super::stats::*;
+use super::text::{capitalize, escape, SpanStyle};
+use super::CRIT_CHANCE_MAX_VALUE;
+
+// chance values like hit chance and crit chance are in hundredths of a percent
+const HIT_CHANCE_MIN: i32 = 5 * 100;
+const HIT_CHANCE_MAX: i32 = 100 * 100;
+
+const ARMOUR_REDUCTION_MAX: i32 = 90;
+const RESIST_MAX: i32 = 75;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum HitKind {
+    Attack,
+    Spell,
+}
+
+// who is reading a description of a hit, the reader is called you and everyone else by name
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Perspective {
+    Attacker,
+    Defender,
+    Observer,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Pools {
+    pub health: i32,
+    pub mana: i32,
+    pub energy_shield: i32,
+}
+
+/// Anything that can attack or be hit.
+pub trait Combatant {
+    fn stats(&self) -> &StatMap;
+    fn modifiers(&self) -> &Modifiers;
+    fn weapon(&self) -> WeaponBase;
+    fn hit_source(&self) -> HitSource; // what the combatant hits with when it isn't told to use anything else
+    fn pools(&self) -> &Pools;
+    fn pools_mut(&mut self) -> &mut Pools;
+    fn ailments(&self) -> &Ailments;
+    fn ailments_mut(&mut self) -> &mut Ailments;
+    fn debuffs(&self) -> &Debuffs;
+    fn debuffs_mut(&mut self) -> &mut Debuffs;
+}
+
+/// Everything that happened during a single hit, from the attacker's rolls to the defender's mitigation.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct HitReport {
+    pub kind: HitKind,
+
+    pub hit_chance: i32,
+    pub hit_roll: i32,
+    pub hit: bool,
+
+    pub base_crit_chance: i32, // weapon or skill crit chance plus flat crit chance
+    pub crit_chance: i32, // final crit chance after increases
+    pub crit_roll: i32,
+    pub crit: bool,
+    pub crit_damage_bonus: i32, // bonus that is applied if the hit is a crit
+
+    // weapon or skill damage after conversion but before any other modifiers, then the increased and more
+    // multipliers applied to each type, more is the total multiplier in percent so 100 changes nothing
+    pub base_damage: DamageMap<DamageRange>,
+    pub increased: DamageMap<i32>,
+    pub more: DamageMap<i32>,
+
+    // damage rolled by the attacker before the defender's mitigation
+    pub damage: DamageMap<i32>,
+
+    // None if the hit was evaded or there was no defender
+    pub mitigation: Option<Mitigation>,
+}
+
+impl HitReport {
+    // one line of markup for the hit, each type of damage the defender took is coloured by its type,
+    // names are written as they read mid sentence, e.g. "the Zombie"
+    pub fn describe(&self, attacker: &str, defender: &str, perspective: Perspective) -> String {
+        let you = perspective == Perspective::Attacker;
+        let attacker = if you { "You".to_owned() } else { capitalize(&escape(attacker)) };
+        let defender = if perspective == Perspective::Defender { "you".to_owned() } else { escape(defender) };
+        let Some(mitigation) = &self.mitigation else {
+            let verb = if you { "miss" } else { "misses" };
+            return format!("{attacker} {verb} {defender}.");
+        };
+
+        let verb = match (you, self.crit) {
+            (true, true) => SpanStyle::Crit.markup("critically hit"),
+            (true, false) => "hit".to_owned(),
+            (false, true) => SpanStyle::Crit.markup("critically hits"),
+            (false, false) => "hits".to_owned(),
+        };
+        let taken = mitigation.taken.iter()
+            .filter(|(_, &amount)| amount > 0)
+            .map(|(kind, amount)| SpanStyle::Damage(kind).markup(&format!("{amount} {}", kind.name())))
+            .collect::<Vec<_>>();
+        let taken = if taken.is_empty() { "no".to_owned() } else { taken.join(", ") };
+
+        format!("{attacker} {verb} {defender} for {taken} damage.")
+    }
+
+    // the range a hit can roll for a damage type, with or without the crit damage bonus
+    pub fn damage_range(&self, kind: DamageType, crit: bool) -> DamageRange {
+        let range = self.base_damage[kind].scaled(self.increased[kind]).scaled(self.more[kind] - 100);
+        if crit {
+            range.scaled(self.crit_damage_bonus)
+        } else {
+            range
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Mitigation {
+    pub armour_reduction: i32, // percent of physical damage prevented by armour
+    pub resists: DamageMap<i32>, // resistance applied to each damage type after capping
+
+    // damage actually taken after armour and resistances
+    pub taken: DamageMap<i32>,
+
+    pub energy_shield_lost: i32,
+    pub health_lost: i32,
+    pub killed: bool,
+
+    // ailments and debuffs the hit inflicted on the defender
+    pub ailments: Vec<Ailment>,
+    pub debuffs: Vec<Debuff>,
+}
+
+// what a hit is rolled from, the attacker's weapon or a skill
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HitSource {
+    pub tags: Vec<Tag>,
+    pub damage: DamageMap<DamageRange>,
+    pub crit_chance: i32,
+    pub base_time: i32, // in milliseconds, before any speed modifiers
+}
+
+impl HitSource {
+    // a plain attack with `weapon`
+    pub fn weapon(weapon: &WeaponBase) -> Self {
+        Self {
+            tags: vec![Tag::Attack],
+            damage: weapon.damage,
+            crit_chance: weapon.crit_chance,
+            base_time: weapon.attack_time,
+        }
+    }
+
+    // spell hits can be unnerved and hindered, everything else counts as an attack
+    pub fn kind(&self) -> HitKind {
+        if self.tags.contains(&Tag::Spell) {
+            HitKind::Spell
+        } else {
+            HitKind::Attack
+        }
+    }
+
+    // milliseconds between uses at normal action speed, attack and cast speed only count for the matching tag
+    pub fn action_time(&self, stats: &StatMap, modifiers: &Modifiers) -> i32 {
+        let mut increases = vec![SkillSpeed];
+        if self.tags.contains(&Tag::Attack) {
+            increases.push(AttackSpeed);
+        }
+        if self.tags.contains(&Tag::Spell) {
+            increases.push(SpellSpeed);
+        }
+        self.base_time * 100 / modifiers.percent(stats, &increases).max(1)
+    }
+}
+
+// roll an attack without a defender, it can't be evaded and nothing mitigates it
+pub fn roll_attack(attacker: &impl Combatant, rand: &mut Rand) -> HitReport {
+    roll_hit_with(attacker, &attacker.hit_source(), rand)
+}
+
+pub fn resolve_hit(attacker: &impl Combatant, defender: &mut impl Combatant, rand: &mut Rand) -> HitReport {
+    resolve_hit_with(attacker, &attacker.hit_source(), defender, rand)
+}
+
+// roll a hit from `source` without a defender
+pub fn roll_hit_with(attacker: &impl Combatant, source: &HitSource, rand: &mut Rand) -> HitReport {
+    let context = Context::new(&source.tags, attacker);
+    let (stats, modifiers) = attacker.modifiers().resolve(attacker.stats(), &context);
+    roll_hit(attacker, source, &stats, &modifiers, &context, 0, rand)
+}
+
+pub fn resolve_hit_with(
+    attacker: &impl Combatant,
+    source: &HitSource,
+    defender: &mut impl Combatant,
+    rand: &mut Rand,
+) -> HitReport {
+    // spells can only be evaded when they are projectiles
+    let evasion = if source.kind() == HitKind::Attack || source.tags.contains(&Tag::Projectile) {
+        stat_value(defender, Evasion) * (100 - defender.debuffs().blind()) / 100
+    } else {
+        0
+    };
+
+    // conditional modifiers are checked once, before the hit changes anything
+    let context = Context::new(&source.tags, attacker).against(defender);
+    let (stats, modifiers) = attacker.modifiers().resolve(attacker.stats(), &context);
+
+    let mut report = roll_hit(attacker, source, &stats, &modifiers, &context, evasion, rand);
+    if report.hit {
+        let mut mitigation = mitigate(defender, &report.damage, report.kind);
+        mitigation.ailments = roll_ailments(&stats, &modifiers, defender, &report.damage, rand);
+        mitigation.debuffs = roll_debuffs(&stats, defender.stats(), report.kind, rand);
+        for ailment in &mitigation.ailments {
+            defender.ailments_mut().apply(ailment.clone());
+        }
+        for debuff in &mitigation.debuffs {
+            defender.debuffs_mut().apply(debuff.clone());
+        }
+        report.mitigation = Some(mitigation);
+    }
+    report
+}
+
+// `stats` and `modifiers` are the attacker's, with the conditional modifiers for `context` already applied
+fn roll_hit(
+    attacker: &impl Combatant,
+    source: &HitSource,
+    stats: &StatMap,
+    modifiers: &Modifiers,
+    context: &Context,
+    evasion: i32,
+    rand: &mut Rand,
+) -> HitReport {
+    let accuracy = modifiers.value(stats, Accuracy, 0) * (100 - attacker.debuffs().blind()) / 100;
+    let hit_chance = hit_chance(accuracy, evasion);
+    let hit_roll = rand.gen_range(0..HIT_CHANCE_MAX);
+    let hit = hit_roll < hit_chance;
+
+    let base_crit_chance = source.crit_chance + stats.get(CritChance);
+    let crit_chance = modifiers.value(stats, CritChance, source.crit_chance);
+
+    // everyone has an inherent 50% crit damage bonus
+    let crit_damage_bonus = 50 + stats.get(CritDamageBonus);
+
+    // attack and spell damage only count for hits with the matching tag,
+    // more modifiers on these generic stats make every type of damage more
+    let mut generic = vec![DamageInc];
+    if context.has(Tag::Attack) {
+        generic.push(AttackInc);
+    }
+    if context.has(Tag::Spell) {
+        generic.push(SpellInc);
+    }
+    let increased = DamageMap::from_fn(|kind| {
+        generic.iter().map(|&stat| modifiers.increase(stats, stat)).sum::<i32>() + modifiers.inc(stats, kind.stat())
+    });
+    let more = DamageMap::from_fn(|kind| {
+        let more = generic.iter().flat_map(|&stat| modifiers.more(stat)).chain(modifiers.more(kind.stat()));
+        apply_more(100, more) as i32
+    });
+
+    let mut report = HitReport {
+        kind: source.kind(),
+        hit_chance,
+        hit_roll,
+        hit,
+        base_crit_chance,
+        crit_chance,
+        crit_roll: 0,
+        crit: false,
+        crit_damage_bonus,
+        base_damage: modifiers.convert(source.damage),
+        increased,
+        more,
+        damage: DamageMap::default(),
+        mitigation: None,
+    };
+
+    if !hit {
+        return report;
+    }
+
+    report.crit_roll = rand.gen_range(0..CRIT_CHANCE_MAX_VALUE);
+    report.crit = report.crit_roll < crit_chance;
+
+    for kind in DamageType::ALL {
+        let range = report.damage_range(kind, report.crit);
+        if range.is_zero() {
+            continue;
+        }
+        report.damage[kind] = rand.gen_range(range.min..=range.max.max(range.min));
+    }
+
+    report
+}
+
+// the final value of a stat after every modifier on the combatant
+pub fn stat_value(combatant: &impl Combatant, stat: Stat) -> i32 {
+    combatant.modifiers().value(combatant.stats(), stat, 0)
+}
+
+pub fn max_pools(stats: &StatMap, modifiers: &Modifiers) -> Pools {
+    Pools {
+        health: modifiers.value(stats, MaxHealth, 0),
+        mana: modifiers.value(stats, MaxMana, 0),
+        energy_shield: modifiers.value(stats, MaxEnergyShield, 0),
+    }
+}
+
+// chance to hit = 1.25 * accuracy / (accuracy + (evasion * 0.2) ^ 0.9), clamped between 5% and 100%
+pub fn hit_chance(accuracy: i32, evasion: i32) -> i32 {
+    if evasion <= 0 {
+        return HIT_CHANCE_MAX;
+    }
+
+    let accuracy = accuracy.max(0) as f32;
+    let evasion = (evasion as f32 * 0.2).powf(0.9);
+    let chance = 1.25 * accuracy / (accuracy + evasion);
+
+    ((chance * HIT_CHANCE_MAX as f32) as i32).clamp(HIT_CHANCE_MIN, HIT_CHANCE_MAX)
+}
+
+// percent of a physical hit prevented = armour / (armour + 5 * damage), up to 90%
+pub fn armour_reduction(armour: i32, damage: i32) -> i32 {
+    if armour <= 0 || damage <= 0 {
+        return 0;
+    }
+
+    let reduction = 100 * armour as i64 / (armour as i64 + 5 * damage as i64);
+    (reduction as i32).min(ARMOUR_REDUCTION_MAX)
+}
+
+pub fn resist(stats: &StatMap, kind: DamageType) -> i32 {
+    let resist = match kind {
+        DamageType::Physical => return 0,
+        DamageType::Lightning => stats.get(LightningResist),
+        DamageType::Cold => stats.get(ColdResist),
+        DamageType::Fire => stats.get(FireResist),
+        DamageType::Chaos => stats.get(ChaosResist),
+    };
+
+    // negative resistance is uncapped and makes you take more damage
+    resist.min(RESIST_MAX)
+}
+
+fn mitigate(defender: &mut impl Combatant, damage: &DamageMap<i32>, kind: HitKind) -> Mitigation {
+    let stats = defender.stats();
+
+    let armour = stat_value(defender, Armour);
+    let armour_reduction = armour_reduction(armour, damage[DamageType::Physical]);
+
+    // shock, intimidate and unnerve make the defender take increased damage
+    let damage_taken = defender.ailments().shock() + defender.debuffs().damage_taken(kind);
+
+    let mut resists = DamageMap::default();
+    let mut taken = DamageMap::default();
+    for (kind, &amount) in damage.iter() {
+        let reduction = match kind {
+            DamageType::Physical => armour_reduction,
+            _ => {
+                resists[kind] = resist(stats, kind);
+                resists[kind]
+            }
+        };
+        taken[kind] = (amount * (100 - reduction) / 100 * (100 + damage_taken) / 100).max(0);
+    }
+
+    let (energy_shield_lost, health_lost) = take_damage(defender.pools_mut(), &taken);
+
+    Mitigation {
+        armour_reduction,
+        resists,
+        taken,
+        energy_shield_lost,
+        health_lost,
+        killed: defender.pools().health <= 0,
+        ailments: Vec::new(),
+        debuffs: Vec::new(),
+    }
+}
+
+// percent of base movement speed, maim and hinder slow movement
+pub fn move_speed(combatant: &impl Combatant) -> i32 {
+    (100 + combatant.stats().get(MoveSpeed) - combatant.debuffs().slow()).max(0)
+}
+
+// energy shield takes damage before health, chaos damage bypasses energy shield
+pub fn take_damage(pools: &mut Pools, taken: &DamageMap<i32>) -> (i32, i32) {
+    let chaos = taken[DamageType::Chaos];
+    let other = taken.total() - chaos;
+
+    let energy_shield_lost = other.min(pools.energy_shield.max(0));
+    let health_lost = other - energy_shield_lost + chaos;
+
+    pools.energy_shield -= energy_shield_lost;
+    pools.health -= health_lost;
+
+    (energy_shield_lost, health_lost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Player;
+    use crate::game::text::StyledLine;
+
+    fn pools() -> Pools {
+        Pools {
+            health: 100,
+            mana: 100,
+            energy_shield: 50,
+        }
+    }
+
+    #[test]
+    fn test_hit_chance() {
+        assert_eq!(hit_chance(0, 0), HIT_CHANCE_MAX);
+        assert_eq!(hit_chance(100, 0), HIT_CHANCE_MAX);
+        assert_eq!(hit_chance(0, 1000), HIT_CHANCE_MIN);
+        assert_eq!(hit_chance(1_000_000, 1000), HIT_CHANCE_MAX);
+
+        let chance = hit_chance(100, 1000);
+        assert!(chance > HIT_CHANCE_MIN && chance < HIT_CHANCE_MAX);
+    }
+
+    #[test]
+    fn test_armour_reduction() {
+        assert_eq!(armour_reduction(0, 100), 0);
+        assert_eq!(armour_reduction(500, 100), 50);
+        assert_eq!(armour_reduction(1_000_000, 1), ARMOUR_REDUCTION_MAX);
+    }
+
+    #[test]
+    fn test_resist_cap() {
+        let mut stats = StatMap::new();
+        stats.add(FireResist, 90);
+        stats.add(ColdResist, -30);
+        assert_eq!(resist(&stats, DamageType::Fire), RESIST_MAX);
+        assert_eq!(resist(&stats, DamageType::Cold), -30);
+        assert_eq!(resist(&stats, DamageType::Physical), 0);
+    }
+
+    #[test]
+    fn test_energy_shield_before_health() {
+        let mut pools = pools();
+        let mut taken = DamageMap::default();
+        taken[DamageType::Fire] = 80;
+
+        assert_eq!(take_damage(&mut pools, &taken), (50, 30));
+        assert_eq!(pools.energy_shield, 0);
+        assert_eq!(pools.health, 70);
+    }
+
+    #[test]
+    fn test_chaos_bypasses_energy_shield() {
+        let mut pools = pools();
+        let mut taken = DamageMap::default();
+        taken[DamageType::Chaos] = 20;
+        taken[DamageType::Cold] = 10;
+
+        assert_eq!(take_damage(&mut pools, &taken), (10, 20));
+        assert_eq!(pools.energy_shield, 40);
+        assert_eq!(pools.health, 80);
+    }
+
+    #[test]
+    fn test_resolve_hit() {
+        let mut rand = Rand::seed_from_u64(0);
+        let attacker = Player::new();
+        let mut defender = Player::new();
+        defender.set_base_stat(Armour, 1000);
+
+        let report = resolve_hit(&attacker, &mut defender, &mut rand);
+        let mitigation = report.mitigation.as_ref().expect("no evasion means the hit always lands");
+
+        assert!(report.hit);
+        assert!(report.damage[DamageType::Physical] > 0);
+        assert!(mitigation.armour_reduction > 0);
+        assert!(mitigation.taken[DamageType::Physical] < report.damage[DamageType::Physical]);
+        assert_eq!(
+            mitigation.energy_shield_lost + mitigation.health_lost,
+            mitigation.taken.total(),
+        );
+        assert_eq!(defender.pools().energy_shield, 100 - mitigation.energy_shield_lost);
+    }
+
+    #[test]
+    fn test_describe_hit() {
+        let mut rand = Rand::seed_from_u64(0);
+        let attacker = Player::new();
+        let mut defender = Player::new();
+        let report = resolve_hit(&attacker, &mut defender, &mut rand);
+        assert!(report.hit && !report.crit);
+
+        let taken = report.mitigation.as_ref().unwrap().taken[DamageType::Physical];
+        let describe = |perspective| StyledLine::parse(&report.describe("the Ghoul", "the Zombie", perspective)).text();
+        assert_eq!(describe(Perspective::Attacker), format!("You hit the Zombie for {taken} physical damage."));
+        assert_eq!(describe(Perspective::Defender), format!("The Ghoul hits you for {taken} physical damage."));
+        assert_eq!(describe(Perspective::Observer), format!("The Ghoul hits the Zombie for {taken} physical damage."));
+    }
+
+    #[test]
+    fn test_spell_hit() {
+        use crate::game::affix::{AffixKind, RolledAffix};
+        use crate::game::item::EquipSlot;
+        use crate::game::skill::SkillBook;
+
+        let mut rand = Rand::seed_from_u64(0);
+        let book = SkillBook::builtin();
+        let mut attacker = Player::new();
+        attacker.set_base_stat(SpellInc, 30);
+        attacker.edit_item(EquipSlot::MainHand, |item| {
+            item.affixes.push(RolledAffix {
+                name: "of Precision".to_owned(),
+                group: "CritChanceLocal".to_owned(),
+                kind: AffixKind::Suffix,
+                modifiers: vec![(CritChanceLocal, 5000)],
+            });
+        });
+        let mut defender = Player::new();
+        defender.set_base_stat(Evasion, 100_000);
+        defender.debuffs_mut().apply(Debuff {
+            kind: DebuffKind::Unnerve,
+            remaining: 1000,
+        });
+
+        // local crit chance only applies to the weapon, spells never use it
+        let nova = book.get("Ice Nova").unwrap().hit_source(&attacker.weapon()).unwrap();
+        let report = resolve_hit_with(&attacker, &nova, &mut defender, &mut rand);
+        let mitigation = report.mitigation.as_ref().expect("spells that aren't projectiles can't be evaded");
+
+        assert_eq!(report.kind, HitKind::Spell);
+        assert_eq!(report.crit_chance, 600);
+        let weapon = attacker.slot(EquipSlot::MainHand).unwrap().weapon.as_ref().unwrap();
+        assert_eq!(roll_attack(&attacker, &mut rand).crit_chance, weapon.crit_chance + 5000);
+        assert_eq!(report.increased[DamageType::Cold], 30);
+        assert_eq!(
+            mitigation.taken[DamageType::Cold],
+            report.damage[DamageType::Cold] * (100 + DEBUFF_EFFECT) / 100,
+        );
+
+        // cast speed makes spells faster but attack speed doesn't
+        attacker.set_base_stat(SpellSpeed, 25);
+        attacker.set_base_stat(AttackSpeed, 100);
+        assert_eq!(nova.action_time(attacker.stats(), attacker.modifiers()), 800);
+    }
+
+    #[test]
+    fn test_roll_attack_within_range() {
+        let mut rand = Rand::seed_from_u64(0);
+        let mut attacker = Player::new();
+        attacker.set_base_stat(PhysicalInc, 50);
+        attacker.set_base_stat(CritChance, 5000);
+
+        for _ in 0..100 {
+            let report = roll_attack(&attacker, &mut rand);
+            let range = report.damage_range(DamageType::Physical, report.crit);
+
+            assert!(report.mitigation.is_none());
+            assert_eq!(report.increased[DamageType::Physical], 50);
+            assert_eq!(report.crit, report.crit_roll < report.crit_chance);
+            assert!(report.damage[DamageType::Physical] >= range.min);
+            assert!(report.damage[DamageType::Physical] <= range.max);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+    Up,
+    Down,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 6] = [
+        Direction::North,
+        Direction::South,
+        Direction::East,
+        Direction::West,
+        Direction::Up,
+        Direction::Down,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::North => "north",
+            Direction::South => "south",
+            Direction::East => "east",
+            Direction::West => "west",
+            Direction::Up => "up",
+            Direction::Down => "down",
+        }
+    }
+
+    // full names and their first letter, "n" is north
+    pub fn parse(word: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|direction| {
+            let name = direction.name();
+            word == name || word == &name[..1]
+        })
+    }
+}
+
+// something the player typed into the console
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    Look,
+    Go(Direction),
+    Attack(String),
+    Take(String),
+    Equip(String),
+    Unequip(String),
+    Inventory,
+    Stats,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    UnknownVerb(String),
+    MissingTarget(&'static str),
+    UnknownDirection(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "Type a command, or help to list them."),
+            ParseError::UnknownVerb(verb) => write!(f, "I don't know how to {verb}."),
+            ParseError::MissingTarget(verb) => write!(f, "What do you want to {verb}?"),
+            ParseError::UnknownDirection(direction) => write!(f, "There is no direction called {direction}."),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// verbs the parser understands, for the help command
+pub const VERBS: &[(&str, &str)] = &[
+    ("look", "describe where you are"),
+    ("go <direction>", "walk north, south, east, west, up or down, the direction on its own works too"),
+    ("attack <monster>", "hit a monster in the room, it hits back if it survives"),
+    ("take <item>", "pick up an item from the ground"),
+    ("equip <item>", "wear or wield an item from your inventory"),
+    ("unequip <item>", "take off an item and put it in your inventory"),
+    ("inventory", "list the items you are carrying"),
+    ("stats", "show your health, mana and equipment"),
+    ("help", "list these commands"),
+];
+
+impl Command {
+    // verbs and directions are case insensitive, targets are everything after the verb and are matched later
+    pub fn parse(line: &str) -> Result<Self, ParseError> {
+        let line = line.trim().to_lowercase();
+        let (verb, rest) = line.split_once(char::is_whitespace).unwrap_or((&line, ""));
+        let target = rest.trim();
+
+        let target_of = |verb: &'static str| {
+            if target.is_empty() {
+                Err(ParseError::MissingTarget(verb))
+            } else {
+                Ok(target.to_owned())
+            }
+        };
+
+        match verb {
+            "" => Err(ParseError::Empty),
+            "help" | "?" => Ok(Command::Help),
+            "look" | "l" => Ok(Command::Look),
+            "go" | "walk" => {
+                let direction = target_of("go")?;
+                Direction::parse(&direction).map(Command::Go).ok_or(ParseError::UnknownDirection(direction))
+            }
+            "attack" | "kill" | "hit" => target_of("attack").map(Command::Attack),
+            "take" | "get" => target_of("take").map(Command::Take),
+            "equip" | "wield" | "wear" => target_of("equip").map(Command::Equip),
+            "unequip" | "remove" => target_of("unequip").map(Command::Unequip),
+            "inventory" | "inv" | "i" => Ok(Command::Inventory),
+            "stats" => Ok(Command::Stats),
+            _ => Direction::parse(verb).map(Command::Go).ok_or(ParseError::UnknownVerb(verb.to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Command::parse("look"), Ok(Command::Look));
+        assert_eq!(Command::parse("  Go North "), Ok(Command::Go(Direction::North)));
+        assert_eq!(Command::parse("s"), Ok(Command::Go(Direction::South)));
+        assert_eq!(Command::parse("attack skeleton archer"), Ok(Command::Attack("skeleton archer".to_owned())));
+        assert_eq!(Command::parse("equip"), Err(ParseError::MissingTarget("equip")));
+        assert_eq!(Command::parse("remove iron ring"), Ok(Command::Unequip("iron ring".to_owned())));
+        assert_eq!(Command::parse("go nowhere"), Err(ParseError::UnknownDirection("nowhere".to_owned())));
+        assert_eq!(Command::parse("dance"), Err(ParseError::UnknownVerb("dance".to_owned())));
+        assert_eq!(Command::parse(""), Err(ParseError::Empty));
+        assert_eq!(Command::parse("dance").unwrap_err().to_string(), "I don't know how to dance.");
+    }
+}
@@ -0,0 +1,50 @@
+use std::collections::VecDeque;
+
+use super::text::StyledLine;
+
+// lines kept in the scrollback, the oldest ones are dropped first
+pub const SCROLLBACK_LINES: usize = 500;
+
+// everything the game printed and the player typed, newest line last
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct Console {
+    lines: VecDeque<StyledLine>,
+}
+
+impl Console {
+    pub fn lines(&self) -> impl Iterator<Item = &StyledLine> {
+        self.lines.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn push(&mut self, line: impl Into<StyledLine>) {
+        self.lines.push_back(line.into());
+        while self.lines.len() > SCROLLBACK_LINES {
+            self.lines.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrollback_limit() {
+        let mut console = Console::default();
+        for i in 0..=SCROLLBACK_LINES {
+            console.push(format!("{i}"));
+        }
+
+        assert_eq!(console.len(), SCROLLBACK_LINES);
+        assert_eq!(console.lines().next().unwrap().text(), "1");
+        assert_eq!(console.lines().last().unwrap().text(), SCROLLBACK_LINES.to_string());
+    }
+}
@@ -0,0 +1,138 @@
+use std::ops::{Index, IndexMut};
+
+use super::stats::*;
+
+// declared in conversion order, damage can only be converted to types that come after it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Deserialize, serde::Serialize)]
+pub enum DamageType {
+    Physical,
+    Lightning,
+    Cold,
+    Fire,
+    Chaos,
+}
+
+pub const DAMAGE_TYPE_COUNT: usize = 5;
+
+impl DamageType {
+    pub const ALL: [DamageType; DAMAGE_TYPE_COUNT] = [
+        DamageType::Physical,
+        DamageType::Lightning,
+        DamageType::Cold,
+        DamageType::Fire,
+        DamageType::Chaos,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DamageType::Physical => "physical",
+            DamageType::Lightning => "lightning",
+            DamageType::Cold => "cold",
+            DamageType::Fire => "fire",
+            DamageType::Chaos => "chaos",
+        }
+    }
+
+    // added damage of this type
+    pub fn stat(self) -> Stat {
+        match self {
+            DamageType::Physical => Physical,
+            DamageType::Lightning => Lightning,
+            DamageType::Cold => Cold,
+            DamageType::Fire => Fire,
+            DamageType::Chaos => Chaos,
+        }
+    }
+
+    fn idx(self) -> usize {
+        self as usize
+    }
+}
+
+/// A value for every damage type, indexable by [`DamageType`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct DamageMap<T>([T; DAMAGE_TYPE_COUNT]);
+
+impl<T> DamageMap<T> {
+    pub fn from_fn(mut f: impl FnMut(DamageType) -> T) -> Self {
+        Self(DamageType::ALL.map(&mut f))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (DamageType, &T)> {
+        DamageType::ALL.into_iter().zip(self.0.iter())
+    }
+}
+
+impl DamageMap<DamageRange> {
+    // damage types that aren't listed deal no damage
+    pub fn from_pairs(pairs: &[(DamageType, DamageRange)]) -> Self {
+        let mut map = Self::default();
+        for &(kind, range) in pairs {
+            map[kind] = range;
+        }
+        map
+    }
+}
+
+impl DamageMap<i32> {
+    pub fn total(&self) -> i32 {
+        self.0.iter().sum()
+    }
+}
+
+impl<T> Index<DamageType> for DamageMap<T> {
+    type Output = T;
+
+    fn index(&self, kind: DamageType) -> &T {
+        &self.0[kind.idx()]
+    }
+}
+
+impl<T> IndexMut<DamageType> for DamageMap<T> {
+    fn index_mut(&mut self, kind: DamageType) -> &mut T {
+        &mut self.0[kind.idx()]
+    }
+}
+
+/// Inclusive damage range, a weapon that deals "15 to 24" damage has `min: 15, max: 24`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct DamageRange {
+    pub min: i32,
+    pub max: i32,
+}
+
+impl DamageRange {
+    pub const fn new(min: i32, max: i32) -> Self {
+        Self { min, max }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.max <= 0
+    }
+
+    // apply an increase in percent to both ends of the range
+    pub fn scaled(self, percent: i32) -> Self {
+        Self {
+            min: self.min * (100 + percent) / 100,
+            max: self.max * (100 + percent) / 100,
+        }
+    }
+}
+
+/// Serde adapter that writes damage as `[(Physical, (min: 18, max: 33))]`, like monsters and skills,
+/// instead of one positional range per damage type. Use with `#[serde(with = "damage_pairs")]`.
+pub mod damage_pairs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    pub fn serialize<S: Serializer>(damage: &DamageMap<DamageRange>, serializer: S) -> Result<S::Ok, S::Error> {
+        let pairs = damage.iter().filter(|(_, range)| !range.is_zero()).map(|(kind, range)| (kind, *range));
+        pairs.collect::<Vec<_>>().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DamageMap<DamageRange>, D::Error> {
+        let pairs = Vec::<(DamageType, DamageRange)>::deserialize(deserializer)?;
+        Ok(DamageMap::from_pairs(&pairs))
+    }
+}
@@ -0,0 +1,181 @@
+use crate::Rand;
+use rand::prelude::*;
+
+use super::combat::HitKind;
+use super::stats::*;
+use super::CRIT_CHANCE_MAX_VALUE;
+
+// every debuff has the same 20% effect
+pub const DEBUFF_EFFECT: i32 = 20;
+const DEBUFF_DURATION: i32 = 4000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum DebuffKind {
+    Intimidate, // increased attack damage taken
+    Unnerve,    // increased spell damage taken
+    Maim,       // reduced movement speed, only from attacks
+    Hinder,     // reduced movement speed, only from spells
+    Blind,      // less evasion and accuracy
+}
+
+impl DebuffKind {
+    pub const ALL: [DebuffKind; 5] = [
+        DebuffKind::Intimidate,
+        DebuffKind::Unnerve,
+        DebuffKind::Maim,
+        DebuffKind::Hinder,
+        DebuffKind::Blind,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DebuffKind::Intimidate => "Intimidate",
+            DebuffKind::Unnerve => "Unnerve",
+            DebuffKind::Maim => "Maim",
+            DebuffKind::Hinder => "Hinder",
+            DebuffKind::Blind => "Blind",
+        }
+    }
+
+    pub fn chance_stat(self) -> Stat {
+        match self {
+            DebuffKind::Intimidate => IntimidateChance,
+            DebuffKind::Unnerve => UnnerveChance,
+            DebuffKind::Maim => MaimChance,
+            DebuffKind::Hinder => HinderChance,
+            DebuffKind::Blind => BlindChance,
+        }
+    }
+
+    pub fn can_inflict(self, kind: HitKind) -> bool {
+        match self {
+            DebuffKind::Maim => kind == HitKind::Attack,
+            DebuffKind::Hinder => kind == HitKind::Spell,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Debuff {
+    pub kind: DebuffKind,
+    pub remaining: i32, // in milliseconds
+}
+
+// debuffs don't stack, inflicting one that is already active refreshes its duration
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Debuffs {
+    active: Vec<Debuff>,
+}
+
+impl Debuffs {
+    pub fn iter(&self) -> impl Iterator<Item = &Debuff> {
+        self.active.iter()
+    }
+
+    pub fn apply(&mut self, debuff: Debuff) {
+        match self.active.iter_mut().find(|other| other.kind == debuff.kind) {
+            Some(other) => other.remaining = other.remaining.max(debuff.remaining),
+            None => self.active.push(debuff),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.active.clear();
+    }
+
+    pub fn has(&self, kind: DebuffKind) -> bool {
+        self.active.iter().any(|debuff| debuff.kind == kind)
+    }
+
+    pub fn tick(&mut self, elapsed: i32) {
+        for debuff in &mut self.active {
+            debuff.remaining -= elapsed;
+        }
+        self.active.retain(|debuff| debuff.remaining > 0);
+    }
+
+    // percent increased damage taken from hits of `kind`
+    pub fn damage_taken(&self, kind: HitKind) -> i32 {
+        let debuff = match kind {
+            HitKind::Attack => DebuffKind::Intimidate,
+            HitKind::Spell => DebuffKind::Unnerve,
+        };
+        if self.has(debuff) { DEBUFF_EFFECT } else { 0 }
+    }
+
+    // maim and hinder don't stack with each other
+    pub fn slow(&self) -> i32 {
+        if self.has(DebuffKind::Maim) || self.has(DebuffKind::Hinder) { DEBUFF_EFFECT } else { 0 }
+    }
+
+    // percent less evasion and accuracy
+    pub fn blind(&self) -> i32 {
+        if self.has(DebuffKind::Blind) { DEBUFF_EFFECT } else { 0 }
+    }
+}
+
+// roll each debuff a hit of `kind` can inflict
+pub fn roll_debuffs(attacker: &StatMap, defender: &StatMap, kind: HitKind, rand: &mut Rand) -> Vec<Debuff> {
+    let mut debuffs = Vec::new();
+
+    for debuff in DebuffKind::ALL.into_iter().filter(|debuff| debuff.can_inflict(kind)) {
+        let chance = attacker.get(debuff.chance_stat());
+        if chance <= 0 || rand.gen_range(0..CRIT_CHANCE_MAX_VALUE) >= chance {
+            continue;
+        }
+
+        // the target's expire speed makes debuffs on it run out faster
+        debuffs.push(Debuff {
+            kind: debuff,
+            remaining: DEBUFF_DURATION * 100 / (100 + defender.get(ExpireSpeed)).max(1),
+        });
+    }
+
+    debuffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_restrictions() {
+        let mut rand = Rand::seed_from_u64(0);
+        let mut attacker = StatMap::new();
+        for debuff in DebuffKind::ALL {
+            attacker.add(debuff.chance_stat(), CRIT_CHANCE_MAX_VALUE);
+        }
+
+        let kinds = |hit| roll_debuffs(&attacker, &StatMap::new(), hit, &mut Rand::seed_from_u64(0))
+            .into_iter()
+            .map(|debuff| debuff.kind)
+            .collect::<Vec<_>>();
+        assert!(kinds(HitKind::Attack).contains(&DebuffKind::Maim));
+        assert!(!kinds(HitKind::Attack).contains(&DebuffKind::Hinder));
+        assert!(kinds(HitKind::Spell).contains(&DebuffKind::Hinder));
+        assert!(!kinds(HitKind::Spell).contains(&DebuffKind::Maim));
+
+        let mut debuffs = Debuffs::default();
+        for debuff in roll_debuffs(&attacker, &StatMap::new(), HitKind::Attack, &mut rand) {
+            debuffs.apply(debuff);
+        }
+        assert_eq!(debuffs.damage_taken(HitKind::Attack), DEBUFF_EFFECT);
+        assert_eq!(debuffs.damage_taken(HitKind::Spell), DEBUFF_EFFECT);
+        assert_eq!(debuffs.slow(), DEBUFF_EFFECT);
+    }
+
+    #[test]
+    fn test_refresh_and_expire() {
+        let mut debuffs = Debuffs::default();
+        debuffs.apply(Debuff { kind: DebuffKind::Blind, remaining: 1000 });
+        debuffs.tick(500);
+        debuffs.apply(Debuff { kind: DebuffKind::Blind, remaining: 1000 });
+        assert_eq!(debuffs.iter().count(), 1);
+
+        debuffs.tick(900);
+        assert_eq!(debuffs.blind(), DEBUFF_EFFECT);
+        debuffs.tick(100);
+        assert_eq!(debuffs.blind(), 0);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::Rand;
+use rand::prelude::*;
+
+use super::combat::*;
+use super::sim::*;
+
+pub const PERCENTILES: [u32; 6] = [10, 25, 50, 75, 90, 99];
+
+// how much damage a combatant dealt, either from attacks rolled on their own or from an encounter
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct DpsSummary {
+    pub attacks: usize,
+    pub hits: usize,
+    pub crits: usize,
+    pub duration: i64, // in milliseconds
+
+    pub hit_rate: f32,    // percent of attacks that hit
+    pub crit_rate: f32,   // percent of hits that crit
+    pub average_hit: f32, // average damage of the attacks that hit
+    pub dps: f32,         // every source of damage dealt, divided by the duration
+
+    // damage dealt by a single hit at each of the PERCENTILES
+    pub percentiles: Vec<(u32, i32)>,
+}
+
+impl DpsSummary {
+    // `hits` is the damage of every attack that hit, and whether it was a crit
+    fn new(attacks: usize, mut hits: Vec<(i32, bool)>, total_damage: i64, duration: i64) -> Self {
+        hits.sort_unstable();
+
+        let percent = |count: usize, of: usize| if of == 0 { 0.0 } else { count as f32 * 100.0 / of as f32 };
+        let crits = hits.iter().filter(|(_, crit)| *crit).count();
+        let hit_damage: i64 = hits.iter().map(|(damage, _)| *damage as i64).sum();
+
+        let percentiles = PERCENTILES.iter()
+            .map(|&percentile| {
+                let index = (hits.len() * percentile as usize / 100).min(hits.len().saturating_sub(1));
+                (percentile, hits.get(index).map_or(0, |(damage, _)| *damage))
+            })
+            .collect();
+
+        Self {
+            attacks,
+            hits: hits.len(),
+            crits,
+            duration,
+            hit_rate: percent(hits.len(), attacks),
+            crit_rate: percent(crits, hits.len()),
+            average_hit: if hits.is_empty() { 0.0 } else { hit_damage as f32 / hits.len() as f32 },
+            dps: if duration <= 0 { 0.0 } else { total_damage as f32 * 1000.0 / duration as f32 },
+            percentiles,
+        }
+    }
+}
+
+// roll `attacks` attacks without a defender, dps assumes the attacker attacks back to back
+pub fn simulate_attacks(attacker: &impl Combatant, attacks: usize, seed: u64) -> DpsSummary {
+    let mut rand = Rand::seed_from_u64(seed);
+
+    let mut hits = Vec::with_capacity(attacks);
+    let mut total_damage = 0;
+    for _ in 0..attacks {
+        let report = roll_attack(attacker, &mut rand);
+        if report.hit {
+            let damage = report.damage.total();
+            total_damage += damage as i64;
+            hits.push((damage, report.crit));
+        }
+    }
+
+    let speed = action_speed(attacker).max(1);
+    let duration = attacks as i64 * attack_time(attacker) as i64 * 100 / speed as i64;
+    DpsSummary::new(attacks, hits, total_damage, duration)
+}
+
+// the damage `side` dealt over the whole encounter, damage over time included
+pub fn summarize_encounter<L: Combatant, R: Combatant>(encounter: &Encounter<'_, L, R>, side: Side) -> DpsSummary {
+    let mut attacks = 0;
+    let mut hits = Vec::new();
+    let mut total_damage = 0;
+
+    for event in &encounter.log {
+        match &event.kind {
+            SimEventKind::Hit(report) if event.side == side => {
+                attacks += 1;
+                if let Some(mitigation) = &report.mitigation {
+                    let damage = mitigation.taken.total();
+                    total_damage += damage as i64;
+                    hits.push((damage, report.crit));
+                }
+            }
+            SimEventKind::DamageOverTime(taken) if event.side != side => {
+                total_damage += taken.total() as i64;
+            }
+            _ => {}
+        }
+    }
+
+    DpsSummary::new(attacks, hits, total_damage, encounter.time() as i64)
+}
+
+// the damage of many attacks rolled without a defender, for looking at how damage is spread out
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Distribution {
+    pub damage: Vec<i32>, // damage of every attack, sorted
+    pub crits: usize,
+    pub crit_chance: i32, // the chance the attacks had to crit, in hundredths of a percent
+}
+
+impl Distribution {
+    pub fn min(&self) -> i32 {
+        self.damage.first().copied().unwrap_or(0)
+    }
+
+    pub fn max(&self) -> i32 {
+        self.damage.last().copied().unwrap_or(0)
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.damage.is_empty() {
+            return 0.0;
+        }
+        self.damage.iter().map(|&damage| damage as f64).sum::<f64>() / self.damage.len() as f64
+    }
+
+    pub fn variance(&self) -> f64 {
+        if self.damage.is_empty() {
+            return 0.0;
+        }
+        let mean = self.mean();
+        self.damage.iter().map(|&damage| (damage as f64 - mean).powi(2)).sum::<f64>() / self.damage.len() as f64
+    }
+
+    // percent of attacks that crit
+    pub fn crit_rate(&self) -> f32 {
+        if self.damage.is_empty() {
+            return 0.0;
+        }
+        self.crits as f32 * 100.0 / self.damage.len() as f32
+    }
+
+    // width of each range when damage is split into `bins` ranges, a range is never narrower than 1 damage
+    pub fn bin_width(&self, bins: usize) -> f64 {
+        ((self.max() - self.min() + 1) as f64 / bins.max(1) as f64).max(1.0)
+    }
+
+    // count the attacks in up to `bins` equal ranges between min and max, returns the start of each range and its count
+    pub fn histogram(&self, bins: usize) -> Vec<(f64, usize)> {
+        let min = self.min() as f64;
+        let width = self.bin_width(bins);
+        let bins = ((self.max() - self.min() + 1) as f64 / width).ceil() as usize;
+
+        let mut counts = vec![0; bins];
+        for &damage in &self.damage {
+            let bin = ((damage as f64 - min) / width) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+        counts.into_iter().enumerate().map(|(i, count)| (min + i as f64 * width, count)).collect()
+    }
+}
+
+// roll `attacks` attacks without a defender, keeping the damage of each one
+pub fn sample_attacks(attacker: &impl Combatant, attacks: usize, seed: u64) -> Distribution {
+    let mut rand = Rand::seed_from_u64(seed);
+    let mut distribution = Distribution::default();
+
+    for _ in 0..attacks {
+        let report = roll_attack(attacker, &mut rand);
+        distribution.crit_chance = report.crit_chance;
+        distribution.damage.push(report.damage.total());
+        if report.crit {
+            distribution.crits += 1;
+        }
+    }
+
+    distribution.damage.sort_unstable();
+    distribution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Player;
+    use crate::game::stats::*;
+
+    #[test]
+    fn test_simulate_attacks() {
+        let mut player = Player::new();
+        player.set_base_stat(CritChance, 500);
+
+        let summary = simulate_attacks(&player, 10_000, 0);
+        assert_eq!(summary, simulate_attacks(&player, 10_000, 0));
+
+        // the short sword deals 15 to 24 and attacks once per second, crits are 10% and deal 50% more
+        assert_eq!(summary.hits, 10_000);
+        assert!((summary.crit_rate - 10.0).abs() < 1.0);
+        assert!(summary.average_hit > 19.5 * 1.04 && summary.average_hit < 19.5 * 1.06);
+        assert!((summary.dps - summary.average_hit).abs() < 0.01);
+        assert!(summary.percentiles.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    }
+
+    #[test]
+    fn test_distribution() {
+        let player = Player::new();
+        let distribution = sample_attacks(&player, 10_000, 0);
+
+        assert_eq!(distribution.crit_chance, 500);
+        assert!((distribution.crit_rate() - 5.0).abs() < 1.0);
+        assert!(distribution.min() >= 15 && distribution.max() <= 36);
+        assert!(distribution.variance() > 0.0);
+
+        let histogram = distribution.histogram(10);
+        assert_eq!(histogram.iter().map(|(_, count)| count).sum::<usize>(), 10_000);
+        assert_eq!(histogram[0].0, distribution.min() as f64);
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+
+use super::item::*;
+
+// inventory spaces a new player has, a stack of currency takes up one space
+pub const INVENTORY_CAPACITY: usize = 24;
+
+// items the player is carrying but not wearing
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Inventory {
+    items: Vec<Item>,
+    capacity: usize,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new(INVENTORY_CAPACITY)
+    }
+}
+
+impl Inventory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            items: Vec::new(),
+            capacity,
+        }
+    }
+
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Item> {
+        self.items.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // spaces that aren't taken by an item or stack
+    pub fn free_spaces(&self) -> usize {
+        self.capacity.saturating_sub(self.items.len())
+    }
+
+    // how many of `item` fit, in stacks of the same currency and in free spaces
+    pub fn room_for(&self, item: &Item) -> u32 {
+        let max_stack = item.class.max_stack();
+        let in_stacks = self.items.iter()
+            .filter(|stack| stack.stacks_with(item))
+            .map(|stack| max_stack.saturating_sub(stack.stack))
+            .sum::<u32>();
+        in_stacks + self.free_spaces() as u32 * max_stack
+    }
+
+    // add `item`, topping up stacks of the same currency before taking a new space,
+    // if not all of it fits nothing is added and the item is given back
+    pub fn add(&mut self, mut item: Item) -> Result<(), Item> {
+        if self.room_for(&item) < item.stack {
+            return Err(item);
+        }
+
+        let max_stack = item.class.max_stack();
+        for stack in &mut self.items {
+            if !stack.stacks_with(&item) {
+                continue;
+            }
+            let moved = item.stack.min(max_stack.saturating_sub(stack.stack));
+            stack.stack += moved;
+            item.stack -= moved;
+            if item.stack == 0 {
+                return Ok(());
+            }
+        }
+
+        while item.stack > max_stack {
+            let mut full = item.clone();
+            full.stack = max_stack;
+            item.stack -= max_stack;
+            self.items.push(full);
+        }
+        self.items.push(item);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, index: usize) -> Item {
+        self.items.remove(index)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EquipError {
+    NotInInventory(usize),
+    EmptySlot(EquipSlot),
+    WrongSlot { class: ItemClass, slot: EquipSlot },
+    // the off hand can't be used while wielding a two-handed weapon
+    OffHandBlocked,
+    InventoryFull,
+}
+
+impl fmt::Display for EquipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EquipError::NotInInventory(index) => write!(f, "There is no item in inventory space {}.", index + 1),
+            EquipError::EmptySlot(slot) => write!(f, "Nothing is equipped in the {} slot.", slot.name().to_lowercase()),
+            EquipError::WrongSlot { class, slot } => {
+                write!(f, "{class:?} items can't be equipped in the {} slot.", slot.name().to_lowercase())
+            }
+            EquipError::OffHandBlocked => write!(f, "Both hands are holding a two-handed weapon."),
+            EquipError::InventoryFull => write!(f, "There is no room in your pack."),
+        }
+    }
+}
+
+impl std::error::Error for EquipError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stacking() {
+        let mut inventory = Inventory::new(3);
+        inventory.add(Item::new("Iron Ring", ItemClass::Ring, 1)).unwrap();
+        inventory.add(Item::new_currency("Chaos Orb", CURRENCY_STACK_SIZE - 5)).unwrap();
+        inventory.add(Item::new_currency("Chaos Orb", 10)).unwrap();
+        assert_eq!(inventory.len(), 3);
+        assert_eq!(inventory.get(1).unwrap().stack, CURRENCY_STACK_SIZE);
+        assert_eq!(inventory.get(2).unwrap().stack, 5);
+
+        assert!(inventory.add(Item::new("Iron Ring", ItemClass::Ring, 1)).is_err());
+    }
+
+    #[test]
+    fn test_full_inventory_with_partial_stack() {
+        let mut inventory = Inventory::new(2);
+        inventory.add(Item::new("Iron Ring", ItemClass::Ring, 1)).unwrap();
+        inventory.add(Item::new_currency("Chaos Orb", CURRENCY_STACK_SIZE - 2)).unwrap();
+
+        // only two more fit on the stack, a failed add leaves the inventory as it was
+        let orbs = Item::new_currency("Chaos Orb", 5);
+        assert_eq!(inventory.room_for(&orbs), 2);
+        assert_eq!(inventory.add(orbs).unwrap_err().stack, 5);
+        assert_eq!(inventory.len(), 2);
+        assert_eq!(inventory.get(1).unwrap().stack, CURRENCY_STACK_SIZE - 2);
+
+        inventory.add(Item::new_currency("Chaos Orb", 2)).unwrap();
+        assert_eq!(inventory.get(1).unwrap().stack, CURRENCY_STACK_SIZE);
+        assert_eq!(inventory.room_for(&Item::new_currency("Chaos Orb", 1)), 0);
+    }
+}
@@ -0,0 +1,263 @@
+use super::affix::{AffixKind, RolledAffix};
+use super::damage::*;
+use super::stats::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize, serde::Serialize)]
+pub enum Rarity {
+    #[default]
+    Normal,
+    Magic,
+    Rare,
+    Unique,
+}
+
+impl Rarity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Rarity::Normal => "Normal",
+            Rarity::Magic => "Magic",
+            Rarity::Rare => "Rare",
+            Rarity::Unique => "Unique",
+        }
+    }
+
+    // the most prefixes, or suffixes, that can be rolled on an item of this rarity
+    pub fn max_affixes(self) -> usize {
+        match self {
+            Rarity::Normal => 0,
+            Rarity::Magic => 1,
+            Rarity::Rare => 3,
+            Rarity::Unique => 0,
+        }
+    }
+}
+
+// the base type of an item, decides which affixes can roll on it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum ItemClass {
+    Sword,
+    Axe,
+    Mace,
+    Dagger,
+    Wand,
+    TwoHandSword,
+    TwoHandAxe,
+    TwoHandMace,
+    Bow,
+    Staff,
+    Shield,
+    Helmet,
+    BodyArmour,
+    Gloves,
+    Boots,
+    Ring,
+    Amulet,
+    Currency, // can't be equipped, stacks in the inventory
+}
+
+impl ItemClass {
+    pub fn is_two_handed(self) -> bool {
+        matches!(
+            self,
+            ItemClass::TwoHandSword | ItemClass::TwoHandAxe | ItemClass::TwoHandMace | ItemClass::Bow | ItemClass::Staff
+        )
+    }
+
+    // the most items of this class that fit in one inventory space
+    pub fn max_stack(self) -> u32 {
+        match self {
+            ItemClass::Currency => CURRENCY_STACK_SIZE,
+            _ => 1,
+        }
+    }
+}
+
+pub const CURRENCY_STACK_SIZE: u32 = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum EquipSlot {
+    MainHand,
+    OffHand,
+    Helmet,
+    BodyArmour,
+    Gloves,
+    Boots,
+    LeftRing,
+    RightRing,
+    Amulet,
+}
+
+impl EquipSlot {
+    pub const ALL: [EquipSlot; 9] = [
+        EquipSlot::MainHand,
+        EquipSlot::OffHand,
+        EquipSlot::Helmet,
+        EquipSlot::BodyArmour,
+        EquipSlot::Gloves,
+        EquipSlot::Boots,
+        EquipSlot::LeftRing,
+        EquipSlot::RightRing,
+        EquipSlot::Amulet,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EquipSlot::MainHand => "Main Hand",
+            EquipSlot::OffHand => "Off Hand",
+            EquipSlot::Helmet => "Helmet",
+            EquipSlot::BodyArmour => "Body Armour",
+            EquipSlot::Gloves => "Gloves",
+            EquipSlot::Boots => "Boots",
+            EquipSlot::LeftRing => "Left Ring",
+            EquipSlot::RightRing => "Right Ring",
+            EquipSlot::Amulet => "Amulet",
+        }
+    }
+
+    // whether an item of `class` can be put in the slot, rings go on either hand
+    pub fn accepts(self, class: ItemClass) -> bool {
+        match self {
+            EquipSlot::MainHand => matches!(
+                class,
+                ItemClass::Sword
+                    | ItemClass::Axe
+                    | ItemClass::Mace
+                    | ItemClass::Dagger
+                    | ItemClass::Wand
+                    | ItemClass::TwoHandSword
+                    | ItemClass::TwoHandAxe
+                    | ItemClass::TwoHandMace
+                    | ItemClass::Bow
+                    | ItemClass::Staff
+            ),
+            EquipSlot::OffHand => class == ItemClass::Shield,
+            EquipSlot::Helmet => class == ItemClass::Helmet,
+            EquipSlot::BodyArmour => class == ItemClass::BodyArmour,
+            EquipSlot::Gloves => class == ItemClass::Gloves,
+            EquipSlot::Boots => class == ItemClass::Boots,
+            EquipSlot::LeftRing | EquipSlot::RightRing => class == ItemClass::Ring,
+            EquipSlot::Amulet => class == ItemClass::Amulet,
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Item {
+    pub name: String,
+    pub class: ItemClass,
+    pub level: i32,
+    #[serde(default)]
+    pub rarity: Rarity,
+    #[serde(default)]
+    pub weapon: Option<WeaponBase>,
+    #[serde(default)]
+    pub affixes: Vec<RolledAffix>,
+    #[serde(default = "single")]
+    pub stack: u32, // how many of the item there are, only currency stacks
+}
+
+fn single() -> u32 {
+    1
+}
+
+impl Item {
+    pub fn new(name: &str, class: ItemClass, level: i32) -> Self {
+        Self {
+            name: name.to_owned(),
+            class,
+            level,
+            rarity: Rarity::Normal,
+            weapon: None,
+            affixes: Vec::new(),
+            stack: 1,
+        }
+    }
+
+    pub fn new_weapon(name: &str, class: ItemClass, level: i32, weapon: WeaponBase) -> Self {
+        Self {
+            weapon: Some(weapon),
+            ..Self::new(name, class, level)
+        }
+    }
+
+    pub fn new_currency(name: &str, stack: u32) -> Self {
+        Self {
+            stack,
+            ..Self::new(name, ItemClass::Currency, 1)
+        }
+    }
+
+    // whether `other` can be added to this item's stack
+    pub fn stacks_with(&self, other: &Item) -> bool {
+        self.class.max_stack() > 1 && self.class == other.class && self.name == other.name
+    }
+
+    pub fn affix_count(&self, kind: AffixKind) -> usize {
+        self.affixes.iter().filter(|affix| affix.kind == kind).count()
+    }
+
+    // every rolled modifier on the item, local modifiers included
+    pub fn modifiers(&self) -> impl Iterator<Item = (Stat, i32)> + '_ {
+        self.affixes.iter().flat_map(|affix| affix.modifiers.iter().copied())
+    }
+
+    // sum the item's global modifiers into `stats`, local modifiers only apply to the item itself
+    pub fn add_stats(&self, stats: &mut StatMap) {
+        for (stat, value) in self.modifiers() {
+            if !is_local(stat) {
+                stats.add(stat, value);
+            }
+        }
+    }
+
+    // the item's weapon properties with its local modifiers applied
+    pub fn local_weapon(&self) -> Option<WeaponBase> {
+        let mut weapon = self.weapon.clone()?;
+
+        let mut local = StatMap::new();
+        for (stat, value) in self.modifiers().filter(|(stat, _)| is_local(*stat)) {
+            local.add(stat, value);
+        }
+
+        let physical = &mut weapon.damage[DamageType::Physical];
+        physical.min += local.get(PhysicalLocal);
+        physical.max += local.get(PhysicalLocal);
+        *physical = physical.scaled(local.get(PhysicalLocalInc));
+
+        weapon.crit_chance += local.get(CritChanceLocal);
+        weapon.crit_chance = weapon.crit_chance * (100 + local.get(CritChanceLocalInc)) / 100;
+
+        Some(weapon)
+    }
+}
+
+// local modifiers change the item they're on instead of the character using it
+fn is_local(stat: Stat) -> bool {
+    matches!(stat, PhysicalLocal | PhysicalLocalInc | CritChanceLocal | CritChanceLocalInc)
+}
+
+// base properties every weapon has before any modifiers are applied
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct WeaponBase {
+    #[serde(with = "damage_pairs")]
+    pub damage: DamageMap<DamageRange>,
+    pub attack_time: i32, // in milliseconds, attacks per second = 1 / (attack_time / 1000)
+    pub crit_chance: i32, // in hundredths of a percent, 500 = 5.00%
+}
+
+impl WeaponBase {
+    pub fn physical(min: i32, max: i32, attack_time: i32, crit_chance: i32) -> Self {
+        let mut damage = DamageMap::default();
+        damage[DamageType::Physical] = DamageRange::new(min, max);
+        Self {
+            damage,
+            attack_time,
+            crit_chance,
+        }
+    }
+
+    // used when nothing is equipped in the main hand
+    pub fn unarmed() -> Self {
+        Self::physical(2, 6, 1200, 0)
+    }
+}
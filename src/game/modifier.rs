@@ -0,0 +1,401 @@
+use super::ailment::AilmentKind;
+use super::combat::*;
+use super::damage::*;
+use super::debuff::DebuffKind;
+use super::stats::*;
+
+// health at or below this percent of maximum counts as low health
+const LOW_HEALTH_PERCENT: i32 = 35;
+
+// a modifier granted by a passive, buff or other source, flat values can also be written as plain (Stat, i32) pairs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Mod {
+    Flat(Stat, i32),
+    Inc(Stat, i32),      // percent increased, every increase to a stat is added together
+    More(Stat, i32),     // percent more, every more modifier multiplies the stat separately
+    Override(Stat, i32), // the stat is set to this value no matter what else modifies it
+    Convert(DamageType, DamageType, i32), // percent of one damage type converted to another
+}
+
+impl Mod {
+    // the modifier as it would be written on an item, "20% more Armour"
+    pub fn describe(self) -> String {
+        match self {
+            Mod::Flat(stat, value) => stat.describe(value),
+            Mod::Inc(stat, value) => format!("{value}% increased {}", scaled_name(stat)),
+            Mod::More(stat, value) if value < 0 => format!("{}% less {}", -value, scaled_name(stat)),
+            Mod::More(stat, value) => format!("{value}% more {}", scaled_name(stat)),
+            Mod::Override(stat, value) => format!("{} is always {}", stat.display_name(), stat.format().value(value)),
+            Mod::Convert(from, to, percent) => format!("{percent}% of {} damage converted to {}", from.name(), to.name()),
+        }
+    }
+}
+
+// what a multiplier on `stat` scales, more DamageInc is "more Damage" rather than "more increased Damage"
+fn scaled_name(stat: Stat) -> &'static str {
+    let name = stat.display_name();
+    name.strip_prefix("increased ").unwrap_or(name)
+}
+
+// what kind of hit or skill something is, tagged modifiers only apply to the ones with matching tags
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum Tag {
+    Attack,
+    Spell,
+    Projectile,
+    Melee,
+    DamageOverTime,
+}
+
+impl Tag {
+    pub fn name(self) -> &'static str {
+        match self {
+            Tag::Attack => "attacks",
+            Tag::Spell => "spells",
+            Tag::Projectile => "projectiles",
+            Tag::Melee => "melee skills",
+            Tag::DamageOverTime => "damage over time",
+        }
+    }
+}
+
+// a situation that has to hold for a conditional modifier to apply
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Condition {
+    FullHealth,
+    LowHealth,
+    FullEnergyShield,
+    EnemyAilment(AilmentKind), // against enemies suffering from the ailment
+    EnemyDebuff(DebuffKind),
+}
+
+impl Condition {
+    pub fn describe(self) -> String {
+        match self {
+            Condition::FullHealth => "while on full health".to_owned(),
+            Condition::LowHealth => "while on low health".to_owned(),
+            Condition::FullEnergyShield => "while on full energy shield".to_owned(),
+            Condition::EnemyAilment(kind) => format!("against enemies with {}", kind.name()),
+            Condition::EnemyDebuff(kind) => format!("against enemies with {}", kind.name()),
+        }
+    }
+
+    pub fn holds(self, context: &Context) -> bool {
+        match self {
+            Condition::FullHealth => context.full_health,
+            Condition::LowHealth => context.low_health,
+            Condition::FullEnergyShield => context.full_energy_shield,
+            Condition::EnemyAilment(kind) => context.enemy_ailments.contains(&kind),
+            Condition::EnemyDebuff(kind) => context.enemy_debuffs.contains(&kind),
+        }
+    }
+}
+
+// a modifier that only applies to hits with all of its tags, while all of its conditions hold
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct ConditionalMod {
+    pub modifier: Mod,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+}
+
+impl ConditionalMod {
+    // "10% more Damage with attacks while on full health"
+    pub fn describe(&self) -> String {
+        let mut text = self.modifier.describe();
+        for tag in &self.tags {
+            text += &format!(" with {}", tag.name());
+        }
+        for condition in &self.conditions {
+            text += " ";
+            text += &condition.describe();
+        }
+        text
+    }
+
+    pub fn applies(&self, context: &Context) -> bool {
+        self.tags.iter().all(|tag| context.tags.contains(tag))
+            && self.conditions.iter().all(|condition| condition.holds(context))
+    }
+}
+
+// everything conditional modifiers are checked against, taken from the combatant using them and their enemy
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Context {
+    pub tags: Vec<Tag>,
+    pub full_health: bool,
+    pub low_health: bool,
+    pub full_energy_shield: bool,
+    pub enemy_ailments: Vec<AilmentKind>,
+    pub enemy_debuffs: Vec<DebuffKind>,
+}
+
+impl Context {
+    pub fn new(tags: &[Tag], combatant: &impl Combatant) -> Self {
+        let pools = combatant.pools();
+        let max = max_pools(combatant.stats(), combatant.modifiers());
+        Self {
+            tags: tags.to_vec(),
+            full_health: pools.health >= max.health,
+            low_health: pools.health * 100 <= max.health * LOW_HEALTH_PERCENT,
+            full_energy_shield: pools.energy_shield >= max.energy_shield,
+            enemy_ailments: Vec::new(),
+            enemy_debuffs: Vec::new(),
+        }
+    }
+
+    pub fn against(mut self, enemy: &impl Combatant) -> Self {
+        self.enemy_ailments = enemy.ailments().iter().map(|ailment| ailment.kind).collect();
+        self.enemy_debuffs = enemy.debuffs().iter().map(|debuff| debuff.kind).collect();
+        self
+    }
+
+    pub fn has(&self, tag: Tag) -> bool {
+        self.tags.contains(&tag)
+    }
+}
+
+// the older stats that are increases of another stat, their values are added to the stat's increased modifiers
+pub fn increased_by(stat: Stat) -> Option<Stat> {
+    match stat {
+        MaxHealth => Some(MaxHealthInc),
+        MaxMana => Some(MaxManaInc),
+        MaxEnergyShield => Some(MaxEnergyShieldInc),
+        Evasion => Some(EvasionInc),
+        Armour => Some(ArmourInc),
+        Accuracy => Some(AccuracyInc),
+        CritChance => Some(CritChanceInc),
+        CritChanceLocal => Some(CritChanceLocalInc),
+        Physical => Some(PhysicalInc),
+        PhysicalLocal => Some(PhysicalLocalInc),
+        Lightning => Some(LightningInc),
+        Cold => Some(ColdInc),
+        Fire => Some(FireInc),
+        Chaos => Some(ChaosInc),
+        _ => None,
+    }
+}
+
+// every modifier on a combatant that isn't flat, flat values stay in the combatant's StatMap
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct Modifiers {
+    inc: StatMap,
+    more: Vec<(Stat, i32)>,
+    overrides: Vec<(Stat, i32)>,
+    conversions: Vec<(DamageType, DamageType, i32)>,
+    conditional: Vec<ConditionalMod>,
+}
+
+impl Modifiers {
+    // flat modifiers go into `stats`, everything else is kept here
+    pub fn add(&mut self, stats: &mut StatMap, modifier: Mod) {
+        match modifier {
+            Mod::Flat(stat, value) => stats.add(stat, value),
+            Mod::Inc(stat, value) => self.inc.add(stat, value),
+            Mod::More(stat, value) => self.more.push((stat, value)),
+            Mod::Override(stat, value) => self.overrides.push((stat, value)),
+            Mod::Convert(from, to, percent) => self.conversions.push((from, to, percent)),
+        }
+    }
+
+    pub fn add_conditional(&mut self, modifier: ConditionalMod) {
+        self.conditional.push(modifier);
+    }
+
+    pub fn add_modifiers(&mut self, other: &Modifiers) {
+        self.inc.add_map(&other.inc);
+        self.more.extend_from_slice(&other.more);
+        self.overrides.extend_from_slice(&other.overrides);
+        self.conversions.extend_from_slice(&other.conversions);
+        self.conditional.extend_from_slice(&other.conditional);
+    }
+
+    pub fn conditional(&self) -> &[ConditionalMod] {
+        &self.conditional
+    }
+
+    // the stats and modifiers with every conditional modifier that applies in `context` added to them
+    pub fn resolve(&self, stats: &StatMap, context: &Context) -> (StatMap, Modifiers) {
+        let mut stats = stats.clone();
+        let mut modifiers = self.clone();
+        modifiers.conditional.clear();
+        for conditional in self.conditional.iter().filter(|conditional| conditional.applies(context)) {
+            modifiers.add(&mut stats, conditional.modifier);
+        }
+        (stats, modifiers)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inc.iter().next().is_none() && self.more.is_empty() && self.overrides.is_empty() && self.conversions.is_empty()
+            && self.conditional.is_empty()
+    }
+
+    // total percent increased, including the stat's older Inc stat
+    pub fn inc(&self, stats: &StatMap, stat: Stat) -> i32 {
+        self.inc.get(stat) + increased_by(stat).map_or(0, |inc| stats.get(inc))
+    }
+
+    // the total of a stat that is itself a percent increase, like DamageInc or AttackSpeed,
+    // inc modifiers add to it and more modifiers are left to whatever it increases
+    pub fn increase(&self, stats: &StatMap, stat: Stat) -> i32 {
+        self.override_of(stat).unwrap_or_else(|| stats.get(stat) + self.inc(stats, stat))
+    }
+
+    // percent of normal after every increase in `increases` and then their more modifiers
+    pub fn percent(&self, stats: &StatMap, increases: &[Stat]) -> i32 {
+        let inc: i32 = increases.iter().map(|&stat| self.increase(stats, stat)).sum();
+        apply_more((100 + inc) as i64, increases.iter().flat_map(|&stat| self.more(stat))) as i32
+    }
+
+    // every more modifier of `stat`, in the order they were added
+    pub fn more(&self, stat: Stat) -> impl Iterator<Item = i32> + '_ {
+        self.more.iter().filter(move |(other, _)| *other == stat).map(|(_, value)| *value)
+    }
+
+    // the last override added wins, buffs are added after everything else so they override passives
+    pub fn override_of(&self, stat: Stat) -> Option<i32> {
+        self.overrides.iter().rev().find(|(other, _)| *other == stat).map(|(_, value)| *value)
+    }
+
+    // (base + flat) * (1 + sum of inc) * product of (1 + more), unless the stat is overridden
+    pub fn value(&self, stats: &StatMap, stat: Stat, base: i32) -> i32 {
+        if let Some(value) = self.override_of(stat) {
+            return value;
+        }
+
+        let value = (base + stats.get(stat)) as i64 * (100 + self.inc(stats, stat)) as i64 / 100;
+        apply_more(value, self.more(stat)) as i32
+    }
+
+    // damage can only be converted down the chain physical -> lightning -> cold -> fire -> chaos,
+    // so converted damage can convert again but never loops back to where it came from
+    pub fn convert(&self, damage: DamageMap<DamageRange>) -> DamageMap<DamageRange> {
+        let mut converted = damage;
+        for from in DamageType::ALL {
+            let conversions = self.conversions.iter()
+                .filter(|(other, to, percent)| *other == from && *to > from && *percent > 0)
+                .collect::<Vec<_>>();
+            let total: i32 = conversions.iter().map(|(_, _, percent)| percent).sum();
+            if total <= 0 {
+                continue;
+            }
+
+            // conversions adding up to more than 100% are scaled down so all of the damage is converted once
+            let source = converted[from];
+            for &&(_, to, percent) in &conversions {
+                let share = percent * 100 / total.max(100);
+                let added = source.scaled(share - 100);
+                converted[to].min += added.min;
+                converted[to].max += added.max;
+            }
+            converted[from] = source.scaled(-total.min(100));
+        }
+        converted
+    }
+}
+
+// multiply `value` by each more modifier in turn
+pub fn apply_more(value: i64, more: impl Iterator<Item = i32>) -> i64 {
+    more.fold(value, |value, more| value * (100 + more) as i64 / 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregation_order() {
+        let mut stats = StatMap::new();
+        let mut modifiers = Modifiers::default();
+        modifiers.add(&mut stats, Mod::Flat(Armour, 50));
+        modifiers.add(&mut stats, Mod::Inc(Armour, 30));
+        modifiers.add(&mut stats, Mod::Flat(ArmourInc, 20));
+        modifiers.add(&mut stats, Mod::More(Armour, 50));
+        modifiers.add(&mut stats, Mod::More(Armour, -20));
+
+        // (50 + 50) * 1.5 * 1.5 * 0.8
+        assert_eq!(modifiers.value(&stats, Armour, 50), 180);
+
+        modifiers.add(&mut stats, Mod::Override(Armour, 0));
+        assert_eq!(modifiers.value(&stats, Armour, 50), 0);
+    }
+
+    #[test]
+    fn test_increase_stats() {
+        use crate::game::combat::HitSource;
+
+        let mut stats = StatMap::new();
+        let mut modifiers = Modifiers::default();
+        modifiers.add(&mut stats, Mod::Flat(AttackSpeed, 20));
+        modifiers.add(&mut stats, Mod::Inc(AttackSpeed, 30));
+        modifiers.add(&mut stats, Mod::More(AttackSpeed, 50));
+        modifiers.add(&mut stats, Mod::Override(SpellSpeed, 0));
+
+        // (100 + 20 + 30) * 1.5
+        assert_eq!(modifiers.increase(&stats, AttackSpeed), 50);
+        assert_eq!(modifiers.percent(&stats, &[SkillSpeed, AttackSpeed]), 225);
+
+        let source = HitSource {
+            tags: vec![Tag::Attack],
+            damage: DamageMap::default(),
+            crit_chance: 0,
+            base_time: 900,
+        };
+        assert_eq!(source.action_time(&stats, &modifiers), 400);
+    }
+
+    #[test]
+    fn test_conversion_chain() {
+        let mut stats = StatMap::new();
+        let mut modifiers = Modifiers::default();
+        modifiers.add(&mut stats, Mod::Convert(DamageType::Physical, DamageType::Lightning, 50));
+        modifiers.add(&mut stats, Mod::Convert(DamageType::Lightning, DamageType::Fire, 100));
+        modifiers.add(&mut stats, Mod::Convert(DamageType::Fire, DamageType::Physical, 100));
+
+        let mut damage = DamageMap::default();
+        damage[DamageType::Physical] = DamageRange::new(100, 200);
+        damage[DamageType::Lightning] = DamageRange::new(10, 10);
+
+        let converted = modifiers.convert(damage);
+        assert_eq!(converted[DamageType::Physical], DamageRange::new(50, 100));
+        assert_eq!(converted[DamageType::Lightning], DamageRange::new(0, 0));
+        assert_eq!(converted[DamageType::Fire], DamageRange::new(60, 110));
+
+        // over 100% is scaled down instead of creating damage
+        modifiers.add(&mut stats, Mod::Convert(DamageType::Physical, DamageType::Cold, 150));
+        let converted = modifiers.convert(damage);
+        assert_eq!(converted[DamageType::Physical], DamageRange::new(0, 0));
+        assert_eq!(converted[DamageType::Cold], DamageRange::new(75, 150));
+        assert_eq!(converted[DamageType::Fire], DamageRange::new(35, 60));
+    }
+
+    #[test]
+    fn test_conditional() {
+        use crate::game::ailment::Ailment;
+        use crate::game::Player;
+
+        let mut player = Player::new();
+        let mut enemy = Player::new();
+        let conditional = ConditionalMod {
+            modifier: Mod::Flat(CritChance, 1000),
+            tags: vec![Tag::Attack],
+            conditions: vec![Condition::FullHealth, Condition::EnemyAilment(AilmentKind::Chill)],
+        };
+        assert_eq!(conditional.describe(), "10.00% Critical Hit Chance with attacks while on full health against enemies with Chill");
+
+        let mut modifiers = Modifiers::default();
+        modifiers.add_conditional(conditional);
+        let crit = |context: &Context| modifiers.resolve(&StatMap::new(), context).0.get(CritChance);
+
+        assert_eq!(crit(&Context::new(&[Tag::Attack], &player).against(&enemy)), 0);
+
+        enemy.ailments_mut().apply(Ailment { kind: AilmentKind::Chill, magnitude: 10, duration: 1000, elapsed: 0 });
+        assert_eq!(crit(&Context::new(&[Tag::Attack], &player).against(&enemy)), 1000);
+        assert_eq!(crit(&Context::new(&[Tag::Spell], &player).against(&enemy)), 0);
+
+        player.pools_mut().health -= 1;
+        assert_eq!(crit(&Context::new(&[Tag::Attack], &player).against(&enemy)), 0);
+    }
+}
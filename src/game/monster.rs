@@ -0,0 +1,249 @@
+use super::ailment::Ailments;
+use super::combat::*;
+use super::damage::*;
+use super::debuff::Debuffs;
+use super::item::{Rarity, WeaponBase};
+use super::modifier::*;
+use super::stats::*;
+
+const BUILTIN_MONSTERS: &str = include_str!("../../data/monsters.ron");
+
+// percent increases per level above 1
+const HEALTH_PER_LEVEL: i32 = 12;
+const DEFENCE_PER_LEVEL: i32 = 8; // armour, evasion and accuracy
+const DAMAGE_PER_LEVEL: i32 = 6;
+
+// monsters gain elemental resistances every few levels
+const RESIST_LEVELS: i32 = 10;
+const RESIST_PER_STEP: i32 = 5;
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct MonsterAttack {
+    #[serde(default)]
+    pub tags: Vec<Tag>, // no tags is a plain attack, casters have the Spell tag
+    pub damage: Vec<(DamageType, DamageRange)>,
+    pub attack_time: i32,
+    pub crit_chance: i32,
+}
+
+// a monster as it is written in data files, all values are for a level 1 normal monster
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct MonsterDef {
+    pub name: String,
+    pub attack: MonsterAttack,
+    pub stats: Vec<(Stat, i32)>,
+    #[serde(default)]
+    pub mods: Vec<Mod>,
+    #[serde(default)]
+    pub conditional: Vec<ConditionalMod>,
+}
+
+impl MonsterDef {
+    pub fn spawn(&self, level: i32, rarity: Rarity) -> Monster {
+        let level = level.max(1);
+        let scale = |value: i32, per_level| value * (100 + (level - 1) * per_level) / 100;
+
+        let mut stats = StatMap::new();
+        for &(stat, value) in &self.stats {
+            let value = match stat {
+                MaxHealth | MaxEnergyShield => scale(value, HEALTH_PER_LEVEL),
+                Armour | Evasion | Accuracy => scale(value, DEFENCE_PER_LEVEL),
+                _ => value,
+            };
+            stats.add(stat, value);
+        }
+
+        let resist = level / RESIST_LEVELS * RESIST_PER_STEP;
+        for stat in [LightningResist, ColdResist, FireResist] {
+            stats.add(stat, resist);
+        }
+
+        for &(stat, value) in rarity_stats(rarity) {
+            stats.add(stat, value);
+        }
+
+        let mut modifiers = Modifiers::default();
+        for &modifier in &self.mods {
+            modifiers.add(&mut stats, modifier);
+        }
+        for conditional in &self.conditional {
+            modifiers.add_conditional(conditional.clone());
+        }
+
+        let damage = DamageMap::from_pairs(&self.attack.damage);
+        let damage = DamageMap::from_fn(|kind| damage[kind].scaled((level - 1) * DAMAGE_PER_LEVEL));
+
+        let pools = max_pools(&stats, &modifiers);
+        Monster {
+            name: self.name.clone(),
+            level,
+            rarity,
+            stats,
+            modifiers,
+            tags: self.attack.tags.clone(),
+            weapon: WeaponBase {
+                damage,
+                attack_time: self.attack.attack_time,
+                crit_chance: self.attack.crit_chance,
+            },
+            pools,
+            ailments: Ailments::default(),
+            debuffs: Debuffs::default(),
+        }
+    }
+}
+
+// stronger monsters are tougher and hit harder
+fn rarity_stats(rarity: Rarity) -> &'static [(Stat, i32)] {
+    match rarity {
+        Rarity::Normal => &[],
+        Rarity::Magic => &[(MaxHealthInc, 100), (DamageInc, 10)],
+        Rarity::Rare => &[
+            (MaxHealthInc, 300),
+            (DamageInc, 25),
+            (LightningResist, 10),
+            (ColdResist, 10),
+            (FireResist, 10),
+        ],
+        Rarity::Unique => &[
+            (MaxHealthInc, 600),
+            (DamageInc, 40),
+            (LightningResist, 20),
+            (ColdResist, 20),
+            (FireResist, 20),
+            (ChaosResist, 10),
+        ],
+    }
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct Bestiary {
+    pub monsters: Vec<MonsterDef>,
+}
+
+impl Bestiary {
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+
+    pub fn builtin() -> Self {
+        Self::from_ron(BUILTIN_MONSTERS).expect("builtin monsters should be valid")
+    }
+
+    pub fn get(&self, name: &str) -> Option<&MonsterDef> {
+        self.monsters.iter().find(|monster| monster.name == name)
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Monster {
+    pub name: String,
+    pub level: i32,
+    pub rarity: Rarity,
+    stats: StatMap,
+    #[serde(default)]
+    modifiers: Modifiers,
+    #[serde(default)]
+    tags: Vec<Tag>,
+    weapon: WeaponBase,
+    pools: Pools,
+    ailments: Ailments,
+    debuffs: Debuffs,
+}
+
+impl Monster {
+    pub fn max_pools(&self) -> Pools {
+        max_pools(&self.stats, &self.modifiers)
+    }
+
+    pub fn restore_pools(&mut self) {
+        self.pools = self.max_pools();
+    }
+}
+
+impl Combatant for Monster {
+    fn stats(&self) -> &StatMap {
+        &self.stats
+    }
+
+    fn modifiers(&self) -> &Modifiers {
+        &self.modifiers
+    }
+
+    fn weapon(&self) -> WeaponBase {
+        self.weapon.clone()
+    }
+
+    fn hit_source(&self) -> HitSource {
+        let mut source = HitSource::weapon(&self.weapon);
+        if !self.tags.is_empty() {
+            source.tags = self.tags.clone();
+        }
+        source
+    }
+
+    fn pools(&self) -> &Pools {
+        &self.pools
+    }
+
+    fn pools_mut(&mut self) -> &mut Pools {
+        &mut self.pools
+    }
+
+    fn ailments(&self) -> &Ailments {
+        &self.ailments
+    }
+
+    fn ailments_mut(&mut self) -> &mut Ailments {
+        &mut self.ailments
+    }
+
+    fn debuffs(&self) -> &Debuffs {
+        &self.debuffs
+    }
+
+    fn debuffs_mut(&mut self) -> &mut Debuffs {
+        &mut self.debuffs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Player;
+    use crate::Rand;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_level_and_rarity_scaling() {
+        let bestiary = Bestiary::builtin();
+        let def = bestiary.get("Zombie").unwrap();
+        let base_health = def.stats.iter().find(|(stat, _)| *stat == MaxHealth).unwrap().1;
+
+        let normal = def.spawn(1, Rarity::Normal);
+        assert_eq!(normal.pools().health, base_health);
+        assert_eq!(normal.stats().get(FireResist), 0);
+
+        let rare = def.spawn(21, Rarity::Rare);
+        assert_eq!(rare.pools().health, base_health * 340 / 100 * 4);
+        assert_eq!(rare.stats().get(FireResist), 20);
+        assert!(rare.weapon().damage[DamageType::Physical].min > normal.weapon().damage[DamageType::Physical].min);
+    }
+
+    #[test]
+    fn test_hits_both_ways() {
+        let mut rand = Rand::seed_from_u64(0);
+        let mut player = Player::new();
+        let mut monster = Bestiary::builtin().get("Zombie").unwrap().spawn(5, Rarity::Magic);
+
+        while monster.pools().health > 0 {
+            resolve_hit(&player, &mut monster, &mut rand);
+        }
+        while player.pools().health > 0 {
+            resolve_hit(&monster, &mut player, &mut rand);
+        }
+
+        monster.restore_pools();
+        assert_eq!(monster.pools(), &monster.max_pools());
+    }
+}
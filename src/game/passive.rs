@@ -0,0 +1,382 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use super::modifier::{ConditionalMod, Mod};
+use super::stats::*;
+
+const BUILTIN_TREE: &str = include_str!("../../data/passive_tree.ron");
+
+pub type NodeId = u32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum NodeKind {
+    Start,
+    Small,
+    Notable,
+    Keystone,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct PassiveNode {
+    pub id: NodeId,
+    pub name: String,
+    pub kind: NodeKind,
+    pub stats: Vec<(Stat, i32)>,
+    #[serde(default)]
+    pub mods: Vec<Mod>, // modifiers that aren't flat, like more or conversion
+    #[serde(default)]
+    pub conditional: Vec<ConditionalMod>,
+    pub links: Vec<NodeId>, // links only have to be listed on one of the two nodes
+    pub position: [f32; 2], // where the node is drawn, the start node is usually at the origin
+}
+
+// the layout of a passive tree as it is written in data files
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct PassiveTreeDef {
+    pub start: NodeId,
+    pub points: u32, // how many points can be allocated, the start node is free
+    pub nodes: Vec<PassiveNode>,
+}
+
+#[derive(Debug)]
+pub enum PassiveError {
+    Parse(ron::error::SpannedError),
+    DuplicateNode(NodeId),
+    UnknownNode(NodeId),
+    AlreadyAllocated(NodeId),
+    NotAllocated(NodeId),
+    NotConnected(NodeId),
+    NoPointsLeft,
+    RefundStart,
+    WouldDisconnect(NodeId),
+}
+
+impl fmt::Display for PassiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PassiveError::Parse(err) => write!(f, "failed to parse passive tree: {err}"),
+            PassiveError::DuplicateNode(id) => write!(f, "node {id} is defined more than once"),
+            PassiveError::UnknownNode(id) => write!(f, "node {id} does not exist"),
+            PassiveError::AlreadyAllocated(id) => write!(f, "node {id} is already allocated"),
+            PassiveError::NotAllocated(id) => write!(f, "node {id} is not allocated"),
+            PassiveError::NotConnected(id) => write!(f, "node {id} is not connected to an allocated node"),
+            PassiveError::NoPointsLeft => write!(f, "no passive points left"),
+            PassiveError::RefundStart => write!(f, "the start node can't be refunded"),
+            PassiveError::WouldDisconnect(id) => {
+                write!(f, "refunding node {id} would disconnect other allocated nodes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PassiveError {}
+
+// saved as just the allocated nodes, the layout is always the builtin tree's so saves pick up changes to it
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(from = "SavedTree", into = "SavedTree")]
+pub struct PassiveTree {
+    def: PassiveTreeDef,
+    index: HashMap<NodeId, usize>,
+    adjacent: Vec<Vec<usize>>,
+    allocated: HashSet<NodeId>,
+}
+
+impl PassiveTree {
+    pub fn new(def: PassiveTreeDef) -> Result<Self, PassiveError> {
+        let mut index = HashMap::new();
+        for (i, node) in def.nodes.iter().enumerate() {
+            if index.insert(node.id, i).is_some() {
+                return Err(PassiveError::DuplicateNode(node.id));
+            }
+        }
+
+        if !index.contains_key(&def.start) {
+            return Err(PassiveError::UnknownNode(def.start));
+        }
+
+        // links are undirected, so each link is added to both nodes
+        let mut adjacent = vec![Vec::new(); def.nodes.len()];
+        for (i, node) in def.nodes.iter().enumerate() {
+            for link in &node.links {
+                let &j = index.get(link).ok_or(PassiveError::UnknownNode(*link))?;
+                if !adjacent[i].contains(&j) {
+                    adjacent[i].push(j);
+                    adjacent[j].push(i);
+                }
+            }
+        }
+
+        let allocated = HashSet::from([def.start]);
+        Ok(Self {
+            def,
+            index,
+            adjacent,
+            allocated,
+        })
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, PassiveError> {
+        let def = ron::from_str(text).map_err(PassiveError::Parse)?;
+        Self::new(def)
+    }
+
+    pub fn builtin() -> Self {
+        Self::from_ron(BUILTIN_TREE).expect("builtin passive tree should be valid")
+    }
+
+    pub fn nodes(&self) -> &[PassiveNode] {
+        &self.def.nodes
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&PassiveNode> {
+        self.index.get(&id).map(|&i| &self.def.nodes[i])
+    }
+
+    // ids of the nodes linked to `id`
+    pub fn neighbours(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let adjacent = self.index.get(&id).map(|&i| self.adjacent[i].as_slice()).unwrap_or(&[]);
+        adjacent.iter().map(|&j| self.def.nodes[j].id)
+    }
+
+    pub fn is_allocated(&self, id: NodeId) -> bool {
+        self.allocated.contains(&id)
+    }
+
+    pub fn points_spent(&self) -> u32 {
+        // the start node is always allocated and doesn't cost a point
+        self.allocated.len() as u32 - 1
+    }
+
+    pub fn points_left(&self) -> u32 {
+        self.def.points.saturating_sub(self.points_spent())
+    }
+
+    pub fn can_allocate(&self, id: NodeId) -> Result<(), PassiveError> {
+        if self.node(id).is_none() {
+            return Err(PassiveError::UnknownNode(id));
+        }
+        if self.is_allocated(id) {
+            return Err(PassiveError::AlreadyAllocated(id));
+        }
+        if self.points_left() == 0 {
+            return Err(PassiveError::NoPointsLeft);
+        }
+        if !self.neighbours(id).any(|other| self.is_allocated(other)) {
+            return Err(PassiveError::NotConnected(id));
+        }
+        Ok(())
+    }
+
+    pub fn allocate(&mut self, id: NodeId) -> Result<(), PassiveError> {
+        self.can_allocate(id)?;
+        self.allocated.insert(id);
+        Ok(())
+    }
+
+    // allocate every node in `ids` in whatever order keeps them connected
+    pub fn allocate_all(&mut self, ids: &[NodeId]) -> Result<(), PassiveError> {
+        let mut remaining = ids.to_vec();
+        while !remaining.is_empty() {
+            let before = remaining.len();
+            remaining.retain(|&id| self.allocate(id).is_err());
+            if remaining.len() == before {
+                return self.allocate(remaining[0]);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn can_refund(&self, id: NodeId) -> Result<(), PassiveError> {
+        if self.node(id).is_none() {
+            return Err(PassiveError::UnknownNode(id));
+        }
+        if id == self.def.start {
+            return Err(PassiveError::RefundStart);
+        }
+        if !self.is_allocated(id) {
+            return Err(PassiveError::NotAllocated(id));
+        }
+
+        // every other allocated node must still be reachable from the start without going through `id`
+        let mut visited = HashSet::from([self.def.start]);
+        let mut queue = VecDeque::from([self.def.start]);
+        while let Some(current) = queue.pop_front() {
+            for next in self.neighbours(current) {
+                if next != id && self.is_allocated(next) && visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        if visited.len() + 1 == self.allocated.len() {
+            Ok(())
+        } else {
+            Err(PassiveError::WouldDisconnect(id))
+        }
+    }
+
+    pub fn refund(&mut self, id: NodeId) -> Result<(), PassiveError> {
+        self.can_refund(id)?;
+        self.allocated.remove(&id);
+        Ok(())
+    }
+
+    pub fn reset(&mut self) {
+        self.allocated = HashSet::from([self.def.start]);
+    }
+
+    // the sum of the stats granted by every allocated node
+    pub fn stats(&self) -> StatMap {
+        let mut stats = StatMap::new();
+        for node in self.def.nodes.iter().filter(|node| self.is_allocated(node.id)) {
+            for &(stat, value) in &node.stats {
+                stats.add(stat, value);
+            }
+        }
+        stats
+    }
+
+    // every modifier granted by the allocated nodes
+    pub fn mods(&self) -> impl Iterator<Item = Mod> + '_ {
+        self.def.nodes.iter()
+            .filter(|node| self.is_allocated(node.id))
+            .flat_map(|node| node.mods.iter().copied())
+    }
+
+    pub fn conditional(&self) -> impl Iterator<Item = &ConditionalMod> {
+        self.def.nodes.iter()
+            .filter(|node| self.is_allocated(node.id))
+            .flat_map(|node| node.conditional.iter())
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct SavedTree {
+    allocated: Vec<NodeId>, // without the start node
+}
+
+impl From<PassiveTree> for SavedTree {
+    fn from(tree: PassiveTree) -> Self {
+        let mut allocated: Vec<_> = tree.allocated.iter().copied().filter(|&id| id != tree.def.start).collect();
+        allocated.sort_unstable();
+        Self { allocated }
+    }
+}
+
+impl From<SavedTree> for PassiveTree {
+    // nodes that were removed from the builtin tree since the save, or no longer connect to the start, are refunded
+    fn from(saved: SavedTree) -> Self {
+        let mut tree = Self::builtin();
+        let mut remaining = saved.allocated;
+        loop {
+            let before = remaining.len();
+            remaining.retain(|&id| tree.allocate(id).is_err());
+            if remaining.len() == before {
+                return tree;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0 - 1 - 2
+    //      \ /
+    //       3 - 4
+    fn tree() -> PassiveTree {
+        let node = |id, links: &[NodeId]| PassiveNode {
+            id,
+            name: format!("Node {id}"),
+            kind: if id == 0 { NodeKind::Start } else { NodeKind::Small },
+            stats: vec![(MaxHealth, 10)],
+            mods: Vec::new(),
+            conditional: Vec::new(),
+            links: links.to_vec(),
+            position: [0.0, 0.0],
+        };
+
+        PassiveTree::new(PassiveTreeDef {
+            start: 0,
+            points: 3,
+            nodes: vec![node(0, &[1]), node(1, &[2, 3]), node(2, &[3]), node(3, &[4]), node(4, &[])],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_allocate() {
+        let mut tree = tree();
+        assert!(matches!(tree.allocate(2), Err(PassiveError::NotConnected(2))));
+
+        tree.allocate(1).unwrap();
+        tree.allocate(3).unwrap();
+        assert!(matches!(tree.allocate(1), Err(PassiveError::AlreadyAllocated(1))));
+
+        tree.allocate(4).unwrap();
+        assert_eq!(tree.points_left(), 0);
+        assert!(matches!(tree.allocate(2), Err(PassiveError::NoPointsLeft)));
+
+        assert_eq!(tree.stats().get(MaxHealth), 40);
+    }
+
+    #[test]
+    fn test_refund_keeps_tree_connected() {
+        let mut def = tree().def;
+        def.points = 4;
+        let mut tree = PassiveTree::new(def).unwrap();
+        for id in [1, 3, 4] {
+            tree.allocate(id).unwrap();
+        }
+
+        assert!(matches!(tree.refund(0), Err(PassiveError::RefundStart)));
+        assert!(matches!(tree.refund(3), Err(PassiveError::WouldDisconnect(3))));
+        assert!(matches!(tree.refund(2), Err(PassiveError::NotAllocated(2))));
+
+        // with 2 allocated there's another path to 3
+        tree.allocate(2).unwrap();
+        tree.refund(1).unwrap_err();
+        tree.refund(4).unwrap();
+        tree.refund(3).unwrap();
+        assert_eq!(tree.points_spent(), 2);
+    }
+
+    #[test]
+    fn test_invalid_definitions() {
+        let mut def = tree().def;
+        def.nodes[4].links.push(9);
+        assert!(matches!(PassiveTree::new(def), Err(PassiveError::UnknownNode(9))));
+
+        let mut def = tree().def;
+        def.nodes[4].id = 3;
+        assert!(matches!(PassiveTree::new(def), Err(PassiveError::DuplicateNode(3))));
+    }
+
+    #[test]
+    fn test_builtin() {
+        let tree = PassiveTree::builtin();
+        assert!(tree.nodes().iter().any(|node| node.kind == NodeKind::Keystone));
+
+        let brute_force = tree.nodes().iter().find(|node| node.name == "Brute Force").unwrap();
+        assert_eq!(brute_force.conditional[0].describe(), "10% more Damage with attacks while on full health");
+    }
+
+    #[test]
+    fn test_saved_allocations() {
+        let mut tree = PassiveTree::builtin();
+        tree.allocate_all(&[11, 10]).unwrap();
+        let saved = ron::to_string(&tree).unwrap();
+        assert_eq!(saved, "(allocated:[10,11])");
+
+        // a node that is no longer in the tree is dropped instead of failing the load
+        let loaded: PassiveTree = ron::from_str("(allocated: [10, 11, 9999])").unwrap();
+        assert!(loaded.is_allocated(10) && loaded.is_allocated(11));
+        assert_eq!(loaded.points_spent(), 2);
+
+        // 11 no longer connects once 10 is gone, so it is refunded as well
+        let loaded: PassiveTree = ron::from_str("(allocated: [11, 20])").unwrap();
+        assert!(loaded.is_allocated(20) && !loaded.is_allocated(11));
+        assert_eq!(loaded.points_spent(), 1);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::Rand;
+use rand::prelude::*;
+
+use super::ailment::tick_ailments;
+use super::combat::*;
+use super::damage::DamageMap;
+use super::stats::*;
+
+// length of a simulation step in milliseconds
+pub const TICK: i32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn name(self) -> &'static str {
+        match self {
+            Side::Left => "Left",
+            Side::Right => "Right",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SimEventKind {
+    Hit(Box<HitReport>),
+    DamageOverTime(DamageMap<i32>),
+    Killed,
+}
+
+// something that happened to `side` at `time`, hits are logged for the attacking side
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimEvent {
+    pub time: i32,
+    pub side: Side,
+    pub kind: SimEventKind,
+}
+
+// percent of normal speed a combatant acts at, chill slows it down and freeze stops it entirely
+pub fn action_speed(combatant: &impl Combatant) -> i32 {
+    if combatant.ailments().is_frozen() {
+        return 0;
+    }
+
+    let speed = combatant.modifiers().percent(combatant.stats(), &[ActionSpeed]);
+    (speed * (100 - combatant.ailments().chill()) / 100).max(0)
+}
+
+// milliseconds between attacks or casts at normal action speed
+pub fn attack_time(combatant: &impl Combatant) -> i32 {
+    combatant.hit_source().action_time(combatant.stats(), combatant.modifiers())
+}
+
+// two combatants attacking each other in fixed steps, the same seed always plays out the same fight
+pub struct Encounter<'a, L, R> {
+    pub left: &'a mut L,
+    pub right: &'a mut R,
+    rand: Rand,
+    time: i32,
+
+    // progress towards each side's next attack, in hundredths of a millisecond at normal speed
+    left_action: i32,
+    right_action: i32,
+
+    pub log: Vec<SimEvent>,
+}
+
+impl<'a, L: Combatant, R: Combatant> Encounter<'a, L, R> {
+    pub fn new(left: &'a mut L, right: &'a mut R, seed: u64) -> Self {
+        Self {
+            left,
+            right,
+            rand: Rand::seed_from_u64(seed),
+            time: 0,
+            left_action: 0,
+            right_action: 0,
+            log: Vec::new(),
+        }
+    }
+
+    pub fn time(&self) -> i32 {
+        self.time
+    }
+
+    pub fn winner(&self) -> Option<Side> {
+        match (self.left.pools().health > 0, self.right.pools().health > 0) {
+            (true, false) => Some(Side::Left),
+            (false, true) => Some(Side::Right),
+            _ => None,
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.left.pools().health <= 0 || self.right.pools().health <= 0
+    }
+
+    // advance the fight by one tick
+    pub fn step(&mut self) {
+        if self.is_over() {
+            return;
+        }
+        self.time += TICK;
+
+        // the left side always acts first within a tick so the order doesn't depend on anything but the seed
+        self.left_action += TICK * action_speed(self.left);
+        if self.left_action >= attack_time(self.left) * 100 {
+            self.left_action -= attack_time(self.left) * 100;
+            let report = resolve_hit(self.left, self.right, &mut self.rand);
+            self.log_hit(Side::Left, report);
+        }
+
+        if !self.is_over() {
+            self.right_action += TICK * action_speed(self.right);
+            if self.right_action >= attack_time(self.right) * 100 {
+                self.right_action -= attack_time(self.right) * 100;
+                let report = resolve_hit(self.right, self.left, &mut self.rand);
+                self.log_hit(Side::Right, report);
+            }
+        }
+
+        let alive = (self.left.pools().health > 0, self.right.pools().health > 0);
+        let left_dot = tick_ailments(self.left, TICK);
+        let right_dot = tick_ailments(self.right, TICK);
+        self.left.debuffs_mut().tick(TICK);
+        self.right.debuffs_mut().tick(TICK);
+        self.log_damage_over_time(Side::Left, left_dot, alive.0 && self.left.pools().health <= 0);
+        self.log_damage_over_time(Side::Right, right_dot, alive.1 && self.right.pools().health <= 0);
+    }
+
+    // step until one side dies or `max_time` milliseconds have passed
+    pub fn run(&mut self, max_time: i32) -> Option<Side> {
+        while !self.is_over() && self.time < max_time {
+            self.step();
+        }
+        self.winner()
+    }
+
+    fn log_hit(&mut self, side: Side, report: HitReport) {
+        let killed = report.mitigation.as_ref().is_some_and(|mitigation| mitigation.killed);
+        self.log.push(SimEvent {
+            time: self.time,
+            side,
+            kind: SimEventKind::Hit(Box::new(report)),
+        });
+        if killed {
+            let defender = if side == Side::Left { Side::Right } else { Side::Left };
+            self.log.push(SimEvent {
+                time: self.time,
+                side: defender,
+                kind: SimEventKind::Killed,
+            });
+        }
+    }
+
+    fn log_damage_over_time(&mut self, side: Side, taken: DamageMap<i32>, killed: bool) {
+        if taken.total() == 0 {
+            return;
+        }
+        self.log.push(SimEvent {
+            time: self.time,
+            side,
+            kind: SimEventKind::DamageOverTime(taken),
+        });
+        if killed {
+            self.log.push(SimEvent {
+                time: self.time,
+                side,
+                kind: SimEventKind::Killed,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::ailment::{Ailment, AilmentKind};
+    use crate::game::item::Rarity;
+    use crate::game::monster::Bestiary;
+    use crate::game::Player;
+
+    fn fight(seed: u64) -> (Option<Side>, i32, Vec<SimEvent>) {
+        let mut player = Player::new();
+        let mut monster = Bestiary::builtin().get("Zombie").unwrap().spawn(10, Rarity::Rare);
+        let mut encounter = Encounter::new(&mut player, &mut monster, seed);
+        let winner = encounter.run(600_000);
+        (winner, encounter.time(), encounter.log)
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let first = fight(7);
+        assert!(first.0.is_some());
+        assert_eq!(first, fight(7));
+    }
+
+    #[test]
+    fn test_attack_speed() {
+        let mut player = Player::new();
+        assert_eq!(attack_time(&player), 1000);
+
+        player.set_base_stat(AttackSpeed, 100);
+        assert_eq!(attack_time(&player), 500);
+
+        player.set_base_stat(ActionSpeed, -50);
+        assert_eq!(action_speed(&player), 50);
+
+        player.ailments_mut().apply(Ailment {
+            kind: AilmentKind::Freeze,
+            magnitude: 0,
+            duration: 1000,
+            elapsed: 0,
+        });
+        assert_eq!(action_speed(&player), 0);
+    }
+
+    #[test]
+    fn test_frozen_combatant_does_not_act() {
+        let mut player = Player::new();
+        let mut monster = Bestiary::builtin().get("Zombie").unwrap().spawn(1, Rarity::Normal);
+        monster.ailments_mut().apply(Ailment {
+            kind: AilmentKind::Freeze,
+            magnitude: 0,
+            duration: 5000,
+            elapsed: 0,
+        });
+
+        let mut encounter = Encounter::new(&mut player, &mut monster, 0);
+        while encounter.time() < 5000 && !encounter.is_over() {
+            encounter.step();
+        }
+        assert!(encounter.log.iter().all(|event| event.side == Side::Left || event.kind == SimEventKind::Killed));
+    }
+}
@@ -0,0 +1,311 @@
+use std::fmt;
+
+use crate::Rand;
+
+use super::combat::*;
+use super::damage::*;
+use super::item::WeaponBase;
+use super::modifier::{Modifiers, Tag};
+use super::stats::*;
+use super::Player;
+
+const BUILTIN_SKILLS: &str = include_str!("../../data/skills.ron");
+
+pub const SKILL_BAR_SLOTS: usize = 4;
+
+// cast time of spells that don't have their own action time, spells don't use the weapon's attack time
+const SPELL_CAST_TIME: i32 = 1000;
+
+// where a skill's damage comes from, before its effectiveness is applied
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum SkillDamage {
+    // persistent skills don't hit, they grant their stats while active
+    #[default]
+    None,
+    // the caster's weapon, local modifiers included
+    Weapon,
+    // the skill's own damage and crit chance, the weapon doesn't matter
+    Base {
+        damage: Vec<(DamageType, DamageRange)>,
+        crit_chance: i32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Skill {
+    pub name: String,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    #[serde(default)]
+    pub damage: SkillDamage,
+    #[serde(default = "full_effectiveness")]
+    pub effectiveness: i32, // percent of the base damage the skill deals, 150 deals half again as much
+    #[serde(default)]
+    pub mana_cost: i32,
+    #[serde(default)]
+    pub reservation: i32, // spirit reserved while a persistent skill is active
+    #[serde(default)]
+    pub cooldown: i32, // in milliseconds
+    #[serde(default)]
+    pub action_time: Option<i32>, // in milliseconds, None uses the weapon's attack time or SPELL_CAST_TIME
+    #[serde(default)]
+    pub stats: Vec<(Stat, i32)>, // granted while a persistent skill is active
+}
+
+fn full_effectiveness() -> i32 {
+    100
+}
+
+impl Skill {
+    // persistent skills are toggled on and off instead of being cast
+    pub fn is_persistent(&self) -> bool {
+        self.damage == SkillDamage::None
+    }
+
+    // what the skill hits with when used with `weapon`, None for persistent skills
+    pub fn hit_source(&self, weapon: &WeaponBase) -> Option<HitSource> {
+        let (damage, crit_chance) = match &self.damage {
+            SkillDamage::None => return None,
+            SkillDamage::Weapon => (weapon.damage, weapon.crit_chance),
+            SkillDamage::Base { damage, crit_chance } => (DamageMap::from_pairs(damage), *crit_chance),
+        };
+
+        let base_time = match self.action_time {
+            Some(time) => time,
+            None if self.tags.contains(&Tag::Spell) => SPELL_CAST_TIME,
+            None => weapon.attack_time,
+        };
+
+        Some(HitSource {
+            tags: self.tags.clone(),
+            damage: DamageMap::from_fn(|kind| damage[kind].scaled(self.effectiveness - 100)),
+            crit_chance,
+            base_time,
+        })
+    }
+
+    // milliseconds it takes `combatant` to use the skill at normal action speed, None for persistent skills
+    pub fn action_time(&self, combatant: &impl Combatant) -> Option<i32> {
+        let source = self.hit_source(&combatant.weapon())?;
+        Some(source.action_time(combatant.stats(), combatant.modifiers()))
+    }
+
+    // milliseconds before the skill can be used again, cooldown recovery makes it shorter
+    pub fn cooldown_time(&self, stats: &StatMap, modifiers: &Modifiers) -> i32 {
+        self.cooldown * 100 / modifiers.percent(stats, &[Cooldown]).max(1)
+    }
+
+    pub fn add_stats(&self, stats: &mut StatMap) {
+        for &(stat, value) in &self.stats {
+            stats.add(stat, value);
+        }
+    }
+}
+
+// every skill that can be put on the skill bar
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct SkillBook {
+    pub skills: Vec<Skill>,
+}
+
+impl SkillBook {
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+
+    pub fn builtin() -> Self {
+        Self::from_ron(BUILTIN_SKILLS).expect("builtin skills should be valid")
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Skill> {
+        self.skills.iter().find(|skill| skill.name == name)
+    }
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct SkillSlot {
+    pub skill: Skill,
+    pub cooldown: i32, // milliseconds until the skill can be used again
+    pub active: bool,  // whether a persistent skill is on
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct SkillBar {
+    slots: [Option<SkillSlot>; SKILL_BAR_SLOTS],
+}
+
+impl SkillBar {
+    pub fn slots(&self) -> &[Option<SkillSlot>] {
+        &self.slots
+    }
+
+    pub fn get(&self, slot: usize) -> Option<&SkillSlot> {
+        self.slots.get(slot)?.as_ref()
+    }
+
+    pub(super) fn get_mut(&mut self, slot: usize) -> Option<&mut SkillSlot> {
+        self.slots.get_mut(slot)?.as_mut()
+    }
+
+    // put `skill` in `slot`, returning the skill that was there before
+    pub fn set(&mut self, slot: usize, skill: Option<Skill>) -> Result<Option<Skill>, SkillError> {
+        let current = self.slots.get_mut(slot).ok_or(SkillError::NoSuchSlot(slot))?;
+        let skill = skill.map(|skill| SkillSlot {
+            skill,
+            cooldown: 0,
+            active: false,
+        });
+        Ok(std::mem::replace(current, skill).map(|slot| slot.skill))
+    }
+
+    // persistent skills that are currently on
+    pub fn active(&self) -> impl Iterator<Item = &Skill> {
+        self.slots.iter().flatten().filter(|slot| slot.active).map(|slot| &slot.skill)
+    }
+
+    // spirit reserved by every active skill
+    pub fn reserved(&self) -> i32 {
+        self.active().map(|skill| skill.reservation).sum()
+    }
+
+    // turn off active skills, last slot first, until they reserve no more than `spirit`,
+    // true if any were turned off
+    pub(super) fn release(&mut self, spirit: i32) -> bool {
+        let mut reserved = self.reserved();
+        let mut released = false;
+        for slot in self.slots.iter_mut().rev().flatten() {
+            if reserved <= spirit {
+                break;
+            }
+            if slot.active {
+                slot.active = false;
+                reserved -= slot.skill.reservation;
+                released = true;
+            }
+        }
+        released
+    }
+
+    pub fn is_cooling_down(&self) -> bool {
+        self.slots.iter().flatten().any(|slot| slot.cooldown > 0)
+    }
+
+    pub fn tick(&mut self, elapsed: i32) {
+        for slot in self.slots.iter_mut().flatten() {
+            slot.cooldown = (slot.cooldown - elapsed).max(0);
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SkillError {
+    NoSuchSlot(usize),
+    EmptySlot(usize),
+    OnCooldown(i32),
+    NotEnoughMana { cost: i32, mana: i32 },
+    NotEnoughSpirit { reservation: i32, unreserved: i32 },
+}
+
+impl fmt::Display for SkillError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkillError::NoSuchSlot(slot) => write!(f, "there is no skill slot {}, the bar has {SKILL_BAR_SLOTS}", slot + 1),
+            SkillError::EmptySlot(slot) => write!(f, "skill slot {} is empty", slot + 1),
+            SkillError::OnCooldown(remaining) => {
+                write!(f, "the skill is on cooldown for another {:.1}s", *remaining as f32 / 1000.0)
+            }
+            SkillError::NotEnoughMana { cost, mana } => write!(f, "the skill costs {cost} mana but only {mana} is left"),
+            SkillError::NotEnoughSpirit { reservation, unreserved } => {
+                write!(f, "the skill reserves {reservation} spirit but only {unreserved} is unreserved")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SkillError {}
+
+// use the skill in `slot` on `defender`, persistent skills are toggled and don't hit anything
+pub fn cast_skill(
+    caster: &mut Player,
+    slot: usize,
+    defender: &mut impl Combatant,
+    rand: &mut Rand,
+) -> Result<Option<HitReport>, SkillError> {
+    let Some(source) = caster.use_skill(slot)? else {
+        return Ok(None);
+    };
+    Ok(Some(resolve_hit_with(caster, &source, defender, rand)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::monster::Bestiary;
+    use crate::game::item::Rarity;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_builtin_skills() {
+        let book = SkillBook::builtin();
+        let weapon = WeaponBase::physical(10, 20, 1000, 500);
+
+        let strike = book.get("Heavy Strike").unwrap().hit_source(&weapon).unwrap();
+        assert_eq!(strike.damage[DamageType::Physical], DamageRange::new(15, 30));
+        assert_eq!(strike.crit_chance, 500);
+
+        let knife = book.get("Throwing Knife").unwrap().hit_source(&weapon).unwrap();
+        assert!(knife.tags.contains(&Tag::Projectile));
+        assert_ne!(knife.crit_chance, weapon.crit_chance);
+
+        assert!(book.skills.iter().any(Skill::is_persistent));
+    }
+
+    #[test]
+    fn test_cast_costs() {
+        let book = SkillBook::builtin();
+        let mut rand = Rand::seed_from_u64(0);
+        let mut player = Player::new();
+        let mut monster = Bestiary::builtin().get("Zombie").unwrap().spawn(1, Rarity::Normal);
+
+        let skill = book.get("Heavy Strike").unwrap().clone();
+        player.set_skill(0, Some(skill.clone())).unwrap();
+        assert_eq!(player.set_skill(SKILL_BAR_SLOTS, Some(skill.clone())), Err(SkillError::NoSuchSlot(SKILL_BAR_SLOTS)));
+        assert_eq!(cast_skill(&mut player, 1, &mut monster, &mut rand), Err(SkillError::EmptySlot(1)));
+
+        let report = cast_skill(&mut player, 0, &mut monster, &mut rand).unwrap().unwrap();
+        assert!(report.damage.total() > 0 || !report.hit);
+        assert_eq!(player.pools().mana, player.max_pools().mana - skill.mana_cost);
+        assert_eq!(player.skill_bar().get(0).unwrap().cooldown, skill.cooldown);
+        assert!(matches!(cast_skill(&mut player, 0, &mut monster, &mut rand), Err(SkillError::OnCooldown(_))));
+
+        player.tick_skills(skill.cooldown);
+        player.pools_mut().mana = 0;
+        assert_eq!(
+            cast_skill(&mut player, 0, &mut monster, &mut rand),
+            Err(SkillError::NotEnoughMana { cost: skill.mana_cost, mana: 0 }),
+        );
+
+        // persistent skills grant their stats while their spirit is reserved
+        let aura = book.skills.iter().find(|skill| skill.is_persistent()).unwrap().clone();
+        player.set_skill(1, Some(aura.clone())).unwrap();
+        player.set_base_stat(MaxSpirit, aura.reservation - 1);
+        assert!(matches!(player.use_skill(1), Err(SkillError::NotEnoughSpirit { .. })));
+
+        player.set_base_stat(MaxSpirit, aura.reservation);
+        assert_eq!(player.use_skill(1), Ok(None));
+        assert_eq!(player.unreserved_spirit(), 0);
+        let (stat, value) = aura.stats[0];
+        assert_eq!(player.stats().get(stat), value);
+
+        assert_eq!(player.use_skill(1), Ok(None));
+        assert_eq!(player.unreserved_spirit(), aura.reservation);
+        assert_eq!(player.stats().get(stat), 0);
+
+        // losing spirit turns off skills that no longer fit
+        assert_eq!(player.use_skill(1), Ok(None));
+        player.set_base_stat(MaxSpirit, aura.reservation - 1);
+        assert!(!player.skill_bar().get(1).unwrap().active);
+        assert_eq!(player.unreserved_spirit(), aura.reservation - 1);
+        assert_eq!(player.stats().get(stat), 0);
+    }
+}
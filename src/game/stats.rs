@@ -1,19 +1,27 @@
 
 pub use Stat::*;
 
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 use crate::sparse::SparseVec;
 
 macro_rules! make_stat_enum {
     (enum $name: ident {
-        $($variant: ident),*,
+        $($(#[doc = $doc: literal])* $variant: ident($category: ident, $format: ident, $display: literal)),*,
     }) => {
-        #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        // stats are saved by name, the discriminants are only used to index stat maps
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
         #[repr(u16)]
         pub enum $name {
-            $($variant),*,
+            $($(#[doc = $doc])* $variant),*,
         }
 
         pub const ALL_STATS: &[Stat] = &[$(Stat::$variant),*];
+        const STAT_NAMES: &[&str] = &[$(stringify!($variant)),*];
 
         impl $name {
             pub fn name(&self) -> &'static str {
@@ -21,130 +29,360 @@ macro_rules! make_stat_enum {
                     $(Self::$variant => stringify!($variant),)*
                 }
             }
+
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $(stringify!($variant) => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+
+            // how the stat is shown to players, "increased Physical Damage" rather than PhysicalInc
+            pub fn display_name(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $display,)*
+                }
+            }
+
+            pub fn category(&self) -> StatCategory {
+                match self {
+                    $(Self::$variant => StatCategory::$category,)*
+                }
+            }
+
+            pub fn format(&self) -> StatFormat {
+                match self {
+                    $(Self::$variant => StatFormat::$format,)*
+                }
+            }
+
+            // the stat's doc comment, shown as a tooltip
+            pub fn description(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => concat!($($doc),*).trim_start(),)*
+                }
+            }
         }
     };
 }
 
-make_stat_enum! {
-    enum Stat {
-        MaxHealth, // health, if health == max health, you are at full health, if health == 0, you are dead
-        MaxHealthInc,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatCategory {
+    Defence,
+    Offence,
+    Ailment,
+    Debuff,
+    Speed,
+}
 
-        MaxMana, // mana, used by most skills, skills drain mana over time according to their mana cost over the duraction of the skill's action time
-        MaxManaInc,
+impl StatCategory {
+    pub const ALL: [StatCategory; 5] = [
+        StatCategory::Defence,
+        StatCategory::Offence,
+        StatCategory::Speed,
+        StatCategory::Ailment,
+        StatCategory::Debuff,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StatCategory::Defence => "Defence",
+            StatCategory::Offence => "Offence",
+            StatCategory::Ailment => "Ailments",
+            StatCategory::Debuff => "Debuffs",
+            StatCategory::Speed => "Speed",
+        }
+    }
+}
 
-        MaxEnergyShield, // energy shield, damage is taken by energy shield before health, chaos damage bypasses energy shield
-        MaxEnergyShieldInc,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatFormat {
+    Flat,
+    Percent,
+    HundredthsPercent, // chances, 500 is 5%
+}
 
-        MaxSpirit, // persistent skills will reserve spirit to maintain their effects
+impl StatFormat {
+    pub fn value(self, value: i32) -> String {
+        match self {
+            StatFormat::Flat => value.to_string(),
+            StatFormat::Percent => format!("{value}%"),
+            StatFormat::HundredthsPercent => format!("{:.2}%", value as f32 / 100.0),
+        }
+    }
+}
 
-        Evasion, // chance to avoid damage from attacks and spell projectiles
-        EvasionInc,
+make_stat_enum! {
+    enum Stat {
+        /// Health, if health == max health you are at full health, if health == 0 you are dead.
+        MaxHealth(Defence, Flat, "maximum Health"),
+        /// Increases maximum health.
+        MaxHealthInc(Defence, Percent, "increased maximum Health"),
+
+        /// Mana is used by most skills, their mana cost is paid in full when they are used.
+        MaxMana(Defence, Flat, "maximum Mana"),
+        /// Increases maximum mana.
+        MaxManaInc(Defence, Percent, "increased maximum Mana"),
+
+        /// Damage is taken by energy shield before health, chaos damage bypasses energy shield.
+        MaxEnergyShield(Defence, Flat, "maximum Energy Shield"),
+        /// Increases maximum energy shield.
+        MaxEnergyShieldInc(Defence, Percent, "increased maximum Energy Shield"),
+
+        /// Persistent skills reserve spirit to maintain their effects.
+        MaxSpirit(Defence, Flat, "maximum Spirit"),
+
+        /// Chance to avoid damage from attacks and spell projectiles.
+        Evasion(Defence, Flat, "Evasion Rating"),
+        /// Increases evasion rating.
+        EvasionInc(Defence, Percent, "increased Evasion Rating"),
+
+        /// Reduces physical damage taken based on its value.
+        Armour(Defence, Flat, "Armour"),
+        /// Increases armour.
+        ArmourInc(Defence, Percent, "increased Armour"),
+
+        /// Chance to hit, compared against the enemy's evasion.
+        Accuracy(Offence, Flat, "Accuracy Rating"),
+        /// Increases accuracy rating.
+        AccuracyInc(Offence, Percent, "increased Accuracy Rating"),
+
+        /// Reduces lightning damage taken, up to 75%.
+        LightningResist(Defence, Percent, "Lightning Resistance"),
+        /// Reduces cold damage taken, up to 75%.
+        ColdResist(Defence, Percent, "Cold Resistance"),
+        /// Reduces fire damage taken, up to 75%.
+        FireResist(Defence, Percent, "Fire Resistance"),
+        /// Reduces chaos damage taken, up to 75%.
+        ChaosResist(Defence, Percent, "Chaos Resistance"),
 
-        Armour, // reduces physical damage taken based on its value
-        ArmourInc,
+        // chance to critically hit, if a hit is a critical hit its damage will be multiplied by the CritDamage stat
+        /// Flat chance to critically hit, added to the weapon's own crit chance.
+        CritChance(Offence, HundredthsPercent, "Critical Hit Chance"),
+        /// Increases critical hit chance.
+        CritChanceInc(Offence, Percent, "increased Critical Hit Chance"),
+        /// Critical hit chance of the weapon it is on.
+        CritChanceLocal(Offence, HundredthsPercent, "Critical Hit Chance on this weapon"),
+        /// Increases the critical hit chance of the weapon it is on.
+        CritChanceLocalInc(Offence, Percent, "increased Critical Hit Chance on this weapon"),
+        /// Extra damage dealt by critical hits.
+        CritDamageBonus(Offence, Percent, "increased Critical Damage Bonus"),
+
+        /// Animation speed, makes you faster or slower at everything.
+        ActionSpeed(Speed, Percent, "increased Action Speed"),
+        /// The speed at which you can move.
+        MoveSpeed(Speed, Percent, "increased Movement Speed"),
+        /// Affects most speed related stats that skills use.
+        SkillSpeed(Speed, Percent, "increased Skill Speed"),
+        /// The speed of attack skills.
+        AttackSpeed(Speed, Percent, "increased Attack Speed"),
+        /// The speed of spell skills.
+        SpellSpeed(Speed, Percent, "increased Cast Speed"),
+        /// The speed at which you can throw traps.
+        TrapThrowingSpeed(Speed, Percent, "increased Trap Throwing Speed"),
+
+        /// Makes ailments, debuffs and other time based effects on you run out faster.
+        ExpireSpeed(Speed, Percent, "increased Expire Speed"),
+        /// Makes cooldowns recover faster.
+        Cooldown(Speed, Percent, "increased Cooldown Recovery Rate"),
+
+        /// Generic damage, it can't be dealt but it scales every type of damage.
+        DamageInc(Offence, Percent, "increased Damage"),
 
-        Accuracy, // chance to hit, is compared to enemy's evasion
-        AccuracyInc,
+        // damage types
+        /// Damage that would physically harm or destroy your body.
+        Physical(Offence, Flat, "added Physical Damage"),
+        /// Increases physical damage.
+        PhysicalInc(Offence, Percent, "increased Physical Damage"),
+        /// Physical damage added to the weapon it is on.
+        PhysicalLocal(Offence, Flat, "added Physical Damage on this weapon"),
+        /// Increases the physical damage of the weapon it is on.
+        PhysicalLocalInc(Offence, Percent, "increased Physical Damage on this weapon"),
+
+        /// Like touching a live AC wire or a tesla coil.
+        Lightning(Offence, Flat, "added Lightning Damage"),
+        /// Increases lightning damage.
+        LightningInc(Offence, Percent, "increased Lightning Damage"),
+
+        /// Touching extremely cold things or being in a cold environment.
+        Cold(Offence, Flat, "added Cold Damage"),
+        /// Increases cold damage.
+        ColdInc(Offence, Percent, "increased Cold Damage"),
+
+        /// Being on fire, or touching something super hot.
+        Fire(Offence, Flat, "added Fire Damage"),
+        /// Increases fire damage.
+        FireInc(Offence, Percent, "increased Fire Damage"),
+
+        /// The opposite of life, themed around the occult or rot.
+        Chaos(Offence, Flat, "added Chaos Damage"),
+        /// Increases chaos damage.
+        ChaosInc(Offence, Percent, "increased Chaos Damage"),
 
-        LightningResist,
-        ColdResist,
-        FireResist,
-        ChaosResist,
+        // damage sources
+        /// Increases damage dealt by attacks, which are almost always made with weapons.
+        AttackInc(Offence, Percent, "increased Attack Damage"),
+        /// Increases damage dealt by spells.
+        SpellInc(Offence, Percent, "increased Spell Damage"),
+        /// Increases damage dealt over time by ailments and debuffs.
+        DamageOverTimeInc(Offence, Percent, "increased Damage over Time"),
+
+        /// Chance for a hit to stun, any hit can stun.
+        StunChance(Offence, HundredthsPercent, "chance to Stun"),
+        /// How hard you are to stun.
+        StunThreshold(Defence, Flat, "Stun Threshold"),
 
-        // chance to critically hit, if a hit is a critical hit its damage will be multiplied by the CritDamage stat
-        CritChance, // flat crit chance
-        CritChanceInc,
-        CritChanceLocal,
-        CritChanceLocalInc,
-        CritDamageBonus,
+        // status ailments
+        /// Increases the damage bleeding deals, bleeding deals physical damage over time.
+        BleedInc(Ailment, Percent, "increased Bleeding Damage"),
+        /// Chance for a hit to inflict bleeding.
+        BleedChance(Ailment, HundredthsPercent, "chance to inflict Bleeding"),
+
+        /// Increases the effect of shock, shocked enemies take increased damage.
+        ShockInc(Ailment, Percent, "increased Shock Effect"),
+        /// Chance for a hit to shock, its effect is based on lightning damage.
+        ShockChance(Ailment, HundredthsPercent, "chance to Shock"),
+
+        /// Increases the effect of chill, chilled enemies act slower.
+        ChillInc(Ailment, Percent, "increased Chill Effect"),
+        /// Chance for a hit to chill, its effect is based on cold damage.
+        ChillChance(Ailment, HundredthsPercent, "chance to Chill"),
+
+        /// Increases the duration of freeze, frozen enemies can't act.
+        FreezeInc(Ailment, Percent, "increased Freeze Duration"),
+        /// Chance for a hit to freeze.
+        FreezeChance(Ailment, HundredthsPercent, "chance to Freeze"),
+
+        /// Increases the damage ignite deals, ignite deals fire damage over time.
+        IgniteInc(Ailment, Percent, "increased Ignite Damage"),
+        /// Chance for a hit to ignite.
+        IgniteChance(Ailment, HundredthsPercent, "chance to Ignite"),
+
+        /// Increases the damage poison deals, poison deals chaos damage over time and stacks.
+        PoisonInc(Ailment, Percent, "increased Poison Damage"),
+        /// Chance for a hit to poison.
+        PoisonChance(Ailment, HundredthsPercent, "chance to Poison"),
 
-        ActionSpeed,       // animation speed, makes you faster or slower at everything
-        MoveSpeed,         // the speed at which you can move
-        SkillSpeed,        // affects most speed related stats that skills use
-        AttackSpeed,       // the speed of attack skills
-        SpellSpeed,        // the speed of spell skills
-        TrapThrowingSpeed, // the speed at which you can throw traps
+        // debuffs
+        /// Chance for a hit to intimidate, intimidated enemies take 20% increased attack damage.
+        IntimidateChance(Debuff, HundredthsPercent, "chance to Intimidate"),
+        /// Chance for a hit to unnerve, unnerved enemies take 20% increased spell damage.
+        UnnerveChance(Debuff, HundredthsPercent, "chance to Unnerve"),
+
+        /// Chance for an attack to maim, maimed enemies move 20% slower.
+        MaimChance(Debuff, HundredthsPercent, "chance to Maim"),
+        /// Chance for a spell to hinder, hindered enemies move 20% slower.
+        HinderChance(Debuff, HundredthsPercent, "chance to Hinder"),
+
+        /// Chance for a hit to blind, blinded enemies have 20% less evasion and accuracy.
+        BlindChance(Debuff, HundredthsPercent, "chance to Blind"),
+    }
+}
 
-        ExpireSpeed, // makes various time based effects last shorter or longer
-        Cooldown,    // makes cooldowns last shorter or longer
+/*
+Burning, // deals fire damage over time, but is not the same as ignite
+Withered, // increased chaos damage taken by 20%
 
-        DamageInc, // generic damage, cannot be dealt but can scale all types of damage
+Secondary, // damage of secondary effects like corpse explosions
+*/
 
-        // damage types
-        Physical, // damage that would physically harm or destroy your body
-        PhysicalInc,
-        PhysicalLocal,
-        PhysicalLocalInc,
+impl Stat {
+    // a value of this stat as it would be written on an item, "40% increased Physical Damage"
+    pub fn describe(self, value: i32) -> String {
+        format!("{} {}", self.format().value(value), self.display_name())
+    }
 
-        Lightning, // like touching a live AC wire or a tesla coil
-        LightningInc,
+    fn idx(self) -> u16 {
+        self as u16
+    }
 
-        Cold, // touching extremely cold things or being in a cold environment
-        ColdInc,
+    fn from_idx(idx: u16) -> Self {
+        ALL_STATS[idx as usize]
+    }
+}
 
-        Fire, // being on fire lol, or touching something super hot
-        FireInc,
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownStat(pub String);
 
-        Chaos, // the opposite of life, themed around the occult or rot
-        ChaosInc,
+impl fmt::Display for UnknownStat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown stat `{}`", self.0)
+    }
+}
 
-        // damage sources
-        AttackInc,         // almost always dealt by actual weapons
-        SpellInc,          // dealt by magic spells
-        DamageOverTimeInc, // dealt by ailments or debuffs
+impl std::error::Error for UnknownStat {}
 
-        StunChance, // stun can be caused by all hits
-        StunThreshold,
+impl FromStr for Stat {
+    type Err = UnknownStat;
 
-        // status ailments
-        BleedInc,    // deals physical damage over time
-        BleedChance, // chance to inflict bleed
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Stat::from_name(name).ok_or_else(|| UnknownStat(name.to_owned()))
+    }
+}
 
-        ShockInc, // makes you take increased damage based on its effectiveness
-        ShockChance, // chance to inflict shock, its effectiveness is based on lightning damage
+// text formats write unit variants by name (ron as a bare identifier), binary formats get the name as a string
+impl Serialize for Stat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_unit_variant("Stat", *self as u32, self.name())
+        } else {
+            serializer.serialize_str(self.name())
+        }
+    }
+}
 
-        ChillInc, // reduces action speed based on its effectiveness
-        ChillChance, // chance to inflict chill, its effectiveness is based on cold damage
+impl<'de> Deserialize<'de> for Stat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_enum("Stat", STAT_NAMES, StatVisitor)
+        } else {
+            deserializer.deserialize_str(StatVisitor)
+        }
+    }
+}
 
-        FreezeInc, // sets action speed to 0
-        FreezeChance, // chance to freeze
+struct StatVisitor;
 
-        IgniteInc, // deals fire damage over time
-        IgniteChance, // chance to inflict ignite
+impl<'de> serde::de::Visitor<'de> for StatVisitor {
+    type Value = Stat;
 
-        PoisonInc, // deals chaos damage over time
-        PoisonChance, // chance to inflict poison
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the name of a stat")
+    }
 
-        // debuffs
-        IntimidateChance, // increased attack damage taken by 20%
-        UnnerveChance,    // increased spell damage taken by 20%
+    fn visit_str<E: serde::de::Error>(self, name: &str) -> Result<Stat, E> {
+        name.parse().map_err(E::custom)
+    }
 
-        MaimChance,   // reduces movement speed by 20%, only caused by attacks
-        HinderChance, // reduces movement speed by 20%, only caused by spells
+    fn visit_enum<A: serde::de::EnumAccess<'de>>(self, data: A) -> Result<Stat, A::Error> {
+        use serde::de::VariantAccess;
 
-        BlindChance, // 20% less evasion and accuracy
+        let (StatName(stat), variant) = data.variant()?;
+        variant.unit_variant()?;
+        Ok(stat)
     }
 }
 
-/*
-Burning, // deals fire damage over time, but is not the same as ignite
-Withered, // increased chaos damage taken by 20%
+// the variant part of a stat, ron only reads bare identifiers through deserialize_identifier
+struct StatName(Stat);
 
-Secondary, // damage of secondary effects like corpse explosions
-*/
-
-impl Stat {
-    fn idx(self) -> u16 {
-        self as u16
+impl<'de> Deserialize<'de> for StatName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_identifier(StatVisitor).map(StatName)
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Debug)]
 pub struct StatMap {
     map: SparseVec<i32>,
 }
 
+impl Default for StatMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl StatMap {
     pub fn new() -> Self {
         Self {
@@ -152,12 +390,13 @@ impl StatMap {
         }
     }
 
-    pub fn get(&self, stat: Stat) -> i32 {
-        self.map.get(stat.idx()).copied().unwrap_or(0)
+    // every stat that has a value, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (Stat, i32)> + '_ {
+        self.map.iter().map(|&(value, index)| (Stat::from_idx(index), value))
     }
 
-    pub fn debug_get_mut(&mut self, stat: Stat) -> &mut i32 {
-        self.map.get_mut(stat.idx()).unwrap()
+    pub fn get(&self, stat: Stat) -> i32 {
+        self.map.get(stat.idx()).copied().unwrap_or(0)
     }
 
     pub fn add(&mut self, stat: Stat, value: i32) {
@@ -166,6 +405,15 @@ impl StatMap {
             .or_insert(value);
     }
 
+    // add every stat in `other` to this map
+    pub fn add_map(&mut self, other: &StatMap) {
+        for &(value, index) in other.map.iter() {
+            self.map.entry(index)
+                .and_modify(|val| *val += value)
+                .or_insert(value);
+        }
+    }
+
     pub fn reset(&mut self, stat: Stat) {
         self.map.remove(stat.idx());
     }
@@ -175,3 +423,60 @@ impl StatMap {
     }
 
 }
+
+// saved as a map from stat name to value so adding stats doesn't shift old saves
+impl Serialize for StatMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let sorted: BTreeMap<Stat, i32> = self.iter().collect();
+        sorted.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for StatMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut stats = StatMap::new();
+        for (stat, value) in BTreeMap::<Stat, i32>::deserialize(deserializer)? {
+            stats.add(stat, value);
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stat_names() {
+        for &stat in ALL_STATS {
+            assert_eq!(stat.name().parse(), Ok(stat));
+        }
+        assert_eq!("Strength".parse::<Stat>(), Err(UnknownStat("Strength".to_owned())));
+
+        let mut stats = StatMap::new();
+        stats.add(CritChance, 500);
+        stats.add(MaxHealth, 100);
+
+        let text = ron::to_string(&stats).unwrap();
+        assert_eq!(text, "{MaxHealth:100,CritChance:500}");
+        assert_eq!(serde_json::to_string(&stats).unwrap(), r#"{"MaxHealth":100,"CritChance":500}"#);
+
+        let loaded: StatMap = ron::from_str(&text).unwrap();
+        assert_eq!(loaded.get(CritChance), 500);
+        assert_eq!(loaded.get(MaxHealth), 100);
+        let loaded: StatMap = serde_json::from_str(r#"{"Armour":5}"#).unwrap();
+        assert_eq!(loaded.get(Armour), 5);
+
+        let err = ron::from_str::<StatMap>("{Strength:10}").unwrap_err();
+        assert!(err.to_string().contains("unknown stat `Strength`"));
+    }
+
+    #[test]
+    fn test_stat_metadata() {
+        assert!(ALL_STATS.iter().all(|stat| !stat.description().is_empty() && !stat.description().starts_with(' ')));
+        assert_eq!(PhysicalInc.describe(40), "40% increased Physical Damage");
+        assert_eq!(CritChance.describe(550), "5.50% Critical Hit Chance");
+        assert_eq!(MaxHealth.category(), StatCategory::Defence);
+        assert_eq!(BleedChance.category(), StatCategory::Ailment);
+    }
+}
@@ -0,0 +1,237 @@
+use super::damage::DamageType;
+use super::item::Rarity;
+
+// game output is written with inline tags, "you take {fire}12 fire{/} damage", and parsed into styled spans.
+// a tag is a style's name in braces, {/} goes back to plain text and {{ is a literal brace
+
+// what a piece of game output is, the console decides what that looks like
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum SpanStyle {
+    #[default]
+    Plain,
+    Header,
+    Input, // lines the player typed
+    Crit,
+    Damage(DamageType),
+    Rarity(Rarity),
+}
+
+impl SpanStyle {
+    const TAGGED: [SpanStyle; 12] = [
+        SpanStyle::Header,
+        SpanStyle::Input,
+        SpanStyle::Crit,
+        SpanStyle::Damage(DamageType::Physical),
+        SpanStyle::Damage(DamageType::Lightning),
+        SpanStyle::Damage(DamageType::Cold),
+        SpanStyle::Damage(DamageType::Fire),
+        SpanStyle::Damage(DamageType::Chaos),
+        SpanStyle::Rarity(Rarity::Normal),
+        SpanStyle::Rarity(Rarity::Magic),
+        SpanStyle::Rarity(Rarity::Rare),
+        SpanStyle::Rarity(Rarity::Unique),
+    ];
+
+    // the name used in markup tags
+    pub fn tag(self) -> &'static str {
+        match self {
+            SpanStyle::Plain => "/",
+            SpanStyle::Header => "header",
+            SpanStyle::Input => "input",
+            SpanStyle::Crit => "crit",
+            SpanStyle::Damage(kind) => kind.name(),
+            SpanStyle::Rarity(Rarity::Normal) => "normal",
+            SpanStyle::Rarity(Rarity::Magic) => "magic",
+            SpanStyle::Rarity(Rarity::Rare) => "rare",
+            SpanStyle::Rarity(Rarity::Unique) => "unique",
+        }
+    }
+
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        if tag == "/" {
+            return Some(SpanStyle::Plain);
+        }
+        Self::TAGGED.into_iter().find(|style| style.tag() == tag)
+    }
+
+    // wrap `text` in this style's tags
+    pub fn markup(self, text: &str) -> String {
+        match self {
+            SpanStyle::Plain => escape(text),
+            _ => format!("{{{}}}{}{{/}}", self.tag(), escape(text)),
+        }
+    }
+
+    // a fixed colour for styles that have one, the others follow the console's theme
+    pub fn rgb(self) -> Option<[u8; 3]> {
+        match self {
+            SpanStyle::Plain | SpanStyle::Header | SpanStyle::Input => None,
+            SpanStyle::Crit => Some([255, 170, 0]),
+            SpanStyle::Damage(DamageType::Physical) => Some([200, 200, 200]),
+            SpanStyle::Damage(DamageType::Lightning) => Some([255, 215, 0]),
+            SpanStyle::Damage(DamageType::Cold) => Some([100, 180, 255]),
+            SpanStyle::Damage(DamageType::Fire) => Some([240, 80, 40]),
+            SpanStyle::Damage(DamageType::Chaos) => Some([210, 80, 200]),
+            SpanStyle::Rarity(Rarity::Normal) => Some([200, 200, 200]),
+            SpanStyle::Rarity(Rarity::Magic) => Some([136, 136, 255]),
+            SpanStyle::Rarity(Rarity::Rare) => Some([255, 255, 119]),
+            SpanStyle::Rarity(Rarity::Unique) => Some([175, 96, 37]),
+        }
+    }
+
+    // ansi select graphic rendition parameters for terminals
+    fn sgr(self) -> String {
+        let mut codes = Vec::new();
+        match self {
+            SpanStyle::Header | SpanStyle::Crit => codes.push("1".to_owned()),
+            SpanStyle::Input => codes.push("2".to_owned()),
+            _ => {}
+        }
+        if let Some([r, g, b]) = self.rgb() {
+            codes.push(format!("38;2;{r};{g};{b}"));
+        }
+        codes.join(";")
+    }
+}
+
+// make text safe to put between markup tags
+pub fn escape(text: &str) -> String {
+    text.replace('{', "{{")
+}
+
+// upper case the first letter, for names that start a sentence
+pub fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    chars.next().map_or(String::new(), |first| first.to_uppercase().chain(chars).collect())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Span {
+    pub text: String,
+    pub style: SpanStyle,
+}
+
+// one line of output made of differently styled pieces
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct StyledLine {
+    pub spans: Vec<Span>,
+}
+
+impl StyledLine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn styled(text: impl Into<String>, style: SpanStyle) -> Self {
+        Self::new().with(text, style)
+    }
+
+    pub fn header(text: impl Into<String>) -> Self {
+        Self::styled(text, SpanStyle::Header)
+    }
+
+    // append a span, neighbouring spans with the same style are merged
+    pub fn push(&mut self, text: impl Into<String>, style: SpanStyle) {
+        let text = text.into();
+        if text.is_empty() {
+            return;
+        }
+        match self.spans.last_mut() {
+            Some(last) if last.style == style => last.text.push_str(&text),
+            _ => self.spans.push(Span { text, style }),
+        }
+    }
+
+    pub fn with(mut self, text: impl Into<String>, style: SpanStyle) -> Self {
+        self.push(text, style);
+        self
+    }
+
+    // the line without any styling
+    pub fn text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    // parse a line of markup, tags that aren't a style's name are kept as text
+    pub fn parse(markup: &str) -> Self {
+        let mut line = Self::new();
+        let mut style = SpanStyle::Plain;
+        let mut rest = markup;
+
+        while let Some(start) = rest.find('{') {
+            line.push(&rest[..start], style);
+            rest = &rest[start..];
+
+            if let Some(after) = rest.strip_prefix("{{") {
+                line.push("{", style);
+                rest = after;
+                continue;
+            }
+
+            let tag = rest[1..].find('}').and_then(|end| Some((SpanStyle::from_tag(&rest[1..end + 1])?, end + 2)));
+            match tag {
+                Some((tagged, len)) => {
+                    style = tagged;
+                    rest = &rest[len..];
+                }
+                None => {
+                    line.push("{", style);
+                    rest = &rest[1..];
+                }
+            }
+        }
+        line.push(rest, style);
+        line
+    }
+
+    // the line written back as markup
+    pub fn to_markup(&self) -> String {
+        self.spans.iter().map(|span| span.style.markup(&span.text)).collect()
+    }
+
+    // the line with ansi escapes, for printing in colour to a terminal
+    pub fn to_ansi(&self) -> String {
+        let mut text = String::new();
+        for span in &self.spans {
+            match span.style.sgr() {
+                codes if codes.is_empty() => text.push_str(&span.text),
+                codes => text.push_str(&format!("\x1b[{codes}m{}\x1b[0m", span.text)),
+            }
+        }
+        text
+    }
+}
+
+impl From<String> for StyledLine {
+    fn from(text: String) -> Self {
+        Self::styled(text, SpanStyle::Plain)
+    }
+}
+
+impl From<&str> for StyledLine {
+    fn from(text: &str) -> Self {
+        Self::styled(text, SpanStyle::Plain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markup() {
+        let line = StyledLine::parse("You take {fire}12 fire{/} damage {{not a tag} {bogus}.");
+        assert_eq!(
+            line.spans,
+            vec![
+                Span { text: "You take ".to_owned(), style: SpanStyle::Plain },
+                Span { text: "12 fire".to_owned(), style: SpanStyle::Damage(DamageType::Fire) },
+                Span { text: " damage {not a tag} {bogus}.".to_owned(), style: SpanStyle::Plain },
+            ],
+        );
+        assert_eq!(StyledLine::parse(&line.to_markup()), line);
+
+        assert_eq!(StyledLine::parse("{crit}big{/}").to_ansi(), "\x1b[1;38;2;255;170;0mbig\x1b[0m");
+        assert_eq!(StyledLine::parse("{unclosed").text(), "{unclosed");
+    }
+}
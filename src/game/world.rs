@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::Rand;
+use rand::prelude::*;
+
+use super::command::Direction;
+use super::item::*;
+use super::monster::{Bestiary, Monster};
+
+const BUILTIN_WORLD: &str = include_str!("../../data/world.ron");
+
+// weights for the rarity of monsters rolled from a spawn table
+const RARITY_WEIGHTS: [(Rarity, u32); 3] = [(Rarity::Normal, 80), (Rarity::Magic, 16), (Rarity::Rare, 4)];
+
+pub type RoomId = usize;
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct SpawnEntry {
+    pub monster: String,
+    pub weight: u32,
+}
+
+// a room as it is written in data files, exits refer to other rooms by id
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct RoomDef {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub exits: Vec<(Direction, String)>,
+    #[serde(default)]
+    pub monsters: Vec<String>, // always spawned
+    #[serde(default)]
+    pub spawns: u32, // monsters rolled from the zone's spawn table
+    #[serde(default)]
+    pub items: Vec<Item>,
+}
+
+// rooms that share an area level and spawn table
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ZoneDef {
+    pub name: String,
+    pub level: i32,
+    #[serde(default)]
+    pub spawns: Vec<SpawnEntry>,
+    pub rooms: Vec<RoomDef>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct WorldDef {
+    pub start: String,
+    pub zones: Vec<ZoneDef>,
+}
+
+#[derive(Debug)]
+pub enum WorldError {
+    Parse(ron::error::SpannedError),
+    DuplicateRoom(String),
+    UnknownRoom(String),
+    UnknownMonster(String),
+    EmptySpawnTable(String),
+}
+
+impl fmt::Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldError::Parse(err) => write!(f, "failed to parse world: {err}"),
+            WorldError::DuplicateRoom(id) => write!(f, "room {id} is defined more than once"),
+            WorldError::UnknownRoom(id) => write!(f, "room {id} does not exist"),
+            WorldError::UnknownMonster(name) => write!(f, "monster {name} is not in the bestiary"),
+            WorldError::EmptySpawnTable(zone) => write!(f, "{zone} has rooms with spawns but nothing to spawn"),
+        }
+    }
+}
+
+impl std::error::Error for WorldError {}
+
+impl WorldDef {
+    pub fn from_ron(text: &str) -> Result<Self, WorldError> {
+        ron::from_str(text).map_err(WorldError::Parse)
+    }
+
+    pub fn builtin() -> Self {
+        Self::from_ron(BUILTIN_WORLD).expect("builtin world should be valid")
+    }
+
+    // create every room and roll its monsters, monsters are as strong as their zone's area level
+    pub fn generate(&self, bestiary: &Bestiary, rand: &mut Rand) -> Result<World, WorldError> {
+        let mut ids = HashMap::new();
+        for room in self.zones.iter().flat_map(|zone| &zone.rooms) {
+            if ids.insert(room.id.as_str(), ids.len()).is_some() {
+                return Err(WorldError::DuplicateRoom(room.id.clone()));
+            }
+        }
+        let resolve = |id: &String| ids.get(id.as_str()).copied().ok_or_else(|| WorldError::UnknownRoom(id.clone()));
+
+        let mut zones = Vec::new();
+        let mut rooms = Vec::new();
+        for (zone_id, zone) in self.zones.iter().enumerate() {
+            let spawn = |name: &String, rarity| match bestiary.get(name) {
+                Some(def) => Ok(def.spawn(zone.level, rarity)),
+                None => Err(WorldError::UnknownMonster(name.clone())),
+            };
+            for entry in &zone.spawns {
+                bestiary.get(&entry.monster).ok_or_else(|| WorldError::UnknownMonster(entry.monster.clone()))?;
+            }
+
+            for def in &zone.rooms {
+                let mut room = Room {
+                    id: def.id.clone(),
+                    name: def.name.clone(),
+                    description: def.description.clone(),
+                    zone: zone_id,
+                    exits: Vec::new(),
+                    monsters: Vec::new(),
+                    items: def.items.clone(),
+                };
+                for (direction, id) in &def.exits {
+                    room.exits.push((*direction, resolve(id)?));
+                }
+                for name in &def.monsters {
+                    room.monsters.push(spawn(name, Rarity::Normal)?);
+                }
+                for _ in 0..def.spawns {
+                    let name = roll_weighted(&zone.spawns, |entry| entry.weight, rand)
+                        .ok_or_else(|| WorldError::EmptySpawnTable(zone.name.clone()))?;
+                    let rarity = roll_weighted(&RARITY_WEIGHTS, |(_, weight)| *weight, rand).map_or(Rarity::Normal, |(rarity, _)| *rarity);
+                    room.monsters.push(spawn(&name.monster, rarity)?);
+                }
+                rooms.push(room);
+            }
+
+            zones.push(Zone {
+                name: zone.name.clone(),
+                level: zone.level,
+            });
+        }
+
+        Ok(World {
+            zones,
+            rooms,
+            start: resolve(&self.start)?,
+        })
+    }
+}
+
+// pick one entry by weight, None if every weight is 0
+fn roll_weighted<'a, T>(entries: &'a [T], weight: impl Fn(&T) -> u32, rand: &mut Rand) -> Option<&'a T> {
+    let total: u32 = entries.iter().map(&weight).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut roll = rand.gen_range(0..total);
+    entries.iter().find(|entry| {
+        if roll < weight(entry) {
+            true
+        } else {
+            roll -= weight(entry);
+            false
+        }
+    })
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Zone {
+    pub name: String,
+    pub level: i32, // area level, the level of every monster spawned in the zone
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Room {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub zone: usize,
+    pub exits: Vec<(Direction, RoomId)>,
+    pub monsters: Vec<Monster>,
+    pub items: Vec<Item>,
+}
+
+impl Room {
+    pub fn exit(&self, direction: Direction) -> Option<RoomId> {
+        self.exits.iter().find(|(exit, _)| *exit == direction).map(|(_, room)| *room)
+    }
+}
+
+// every room the player can walk between and what is left in them, saved with the game
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct World {
+    pub zones: Vec<Zone>,
+    pub rooms: Vec<Room>,
+    pub start: RoomId,
+}
+
+impl World {
+    pub fn builtin(rand: &mut Rand) -> Self {
+        WorldDef::builtin()
+            .generate(&Bestiary::builtin(), rand)
+            .expect("builtin world should only use builtin monsters")
+    }
+
+    pub fn room(&self, id: RoomId) -> &Room {
+        &self.rooms[id]
+    }
+
+    pub fn room_mut(&mut self, id: RoomId) -> &mut Room {
+        &mut self.rooms[id]
+    }
+
+    pub fn zone(&self, room: RoomId) -> &Zone {
+        &self.zones[self.rooms[room].zone]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_world() {
+        let mut rand = Rand::seed_from_u64(0);
+        let world = World::builtin(&mut rand);
+
+        assert_eq!(world.room(world.start).name, "Camp");
+        for room in &world.rooms {
+            let level = world.zones[room.zone].level;
+            assert!(room.monsters.iter().all(|monster| monster.level == level));
+            assert!(room.exits.iter().all(|(_, exit)| *exit < world.rooms.len()));
+        }
+        assert!(world.zones.windows(2).all(|pair| pair[0].level < pair[1].level));
+    }
+
+    #[test]
+    fn test_invalid_world() {
+        let bestiary = Bestiary::builtin();
+        let mut rand = Rand::seed_from_u64(0);
+        let mut world = |text| WorldDef::from_ron(text).unwrap().generate(&bestiary, &mut rand).err();
+
+        let unknown_exit = r#"(start: "a", zones: [(name: "Z", level: 1, rooms: [
+            (id: "a", name: "A", description: "", exits: [(North, "b")]),
+        ])])"#;
+        assert!(matches!(world(unknown_exit), Some(WorldError::UnknownRoom(id)) if id == "b"));
+
+        let no_spawns = r#"(start: "a", zones: [(name: "Z", level: 1, rooms: [
+            (id: "a", name: "A", description: "", spawns: 1),
+        ])])"#;
+        assert!(matches!(world(no_spawns), Some(WorldError::EmptySpawnTable(_))));
+    }
+}
@@ -1,10 +1,41 @@
 use egui::*;
 
+use crate::game::console::Console;
+use crate::game::text::*;
+use crate::game::Game;
+use crate::monitor::*;
+
+// the game and its scrollback are saved, the half typed input line isn't
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct GameApp {
-    monitor: String,
+    game: Game,
+    console: Console,
+    #[serde(skip)]
+    input: String,
+}
+
+impl Default for GameApp {
+    fn default() -> Self {
+        let mut game = Game::default();
+        let mut console = Console::default();
+        console.push("Type help to list the commands.");
+        for line in game.run("look") {
+            console.push(line);
+        }
+        Self {
+            game,
+            console,
+            input: String::new(),
+        }
+    }
 }
 
 impl eframe::App for GameApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, self);
+    }
+
     fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
         self.top_panel(ctx);
         self.game_panel(ctx);
@@ -13,21 +44,18 @@ impl eframe::App for GameApp {
 
 impl GameApp {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-
-        
+        load_inconsolata(&cc.egui_ctx);
 
         // This is also where you can customize the look and feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(_storage) = cc.storage {
-            //return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        if let Some(storage) = cc.storage {
+            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
         }
 
-        GameApp {
-            monitor: String::new()
-        }
+        GameApp::default()
     }
 
     fn top_panel(&mut self, ctx: &Context) {
@@ -59,15 +87,42 @@ impl GameApp {
             .movable(false)
             .resizable(false)
             .show(ctx, |ui| {
-                ui.set_min_size([800.0, 600.0].into());
-                ui.label("1. example with 10 lines\n2.\n3.\n4.\n5.\n6.\n7.\n8.\n9.\n10.");
+                // every character is the same size so the monitor is sized as a grid of them
+                let font = FontId::monospace(MONITOR_FONT_SIZE);
+                let cell = ui.fonts(|fonts| vec2(fonts.glyph_width(&font, 'M'), fonts.row_height(&font)));
+                let grid = grid_size(cell);
+                ui.set_width(grid.x);
+
+                ScrollArea::vertical()
+                    .auto_shrink(false)
+                    .max_height(grid.y)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        ui.spacing_mut().item_spacing.y = 0.0;
+                        for line in self.console.lines() {
+                            ui.label(layout_line(ui.visuals(), line, &font, cell.x));
+                        }
+                    });
+
+                let response = ui.add(
+                    TextEdit::singleline(&mut self.input)
+                        .font(FontId::monospace(MONITOR_FONT_SIZE))
+                        .desired_width(f32::INFINITY)
+                        .hint_text("type a command"),
+                );
+                if response.lost_focus() && ui.input(|input| input.key_pressed(Key::Enter)) {
+                    self.submit();
+                    response.request_focus();
+                }
         });
     }
-}
-
-pub const INCONSOLATA: &'_ str = "Inconsolata";
 
-fn load_inconsolata(ctx: &Context) {
-    let data = include_bytes!("../Inconsolata/Inconsolata-VariableFont_wdth,wght.ttf");
-    
+    // echo the typed line and run it
+    fn submit(&mut self) {
+        let line = std::mem::take(&mut self.input);
+        self.console.push(StyledLine::styled(format!("> {line}"), SpanStyle::Input));
+        for output in self.game.run(&line) {
+            self.console.push(output);
+        }
+    }
 }
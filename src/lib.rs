@@ -1,12 +1,17 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+#[cfg(feature = "gui")]
 mod game_app;
-mod game;
+pub mod game;
+#[cfg(feature = "gui")]
+mod monitor;
 mod sparse;
 
+#[cfg(feature = "gui")]
 mod template_app;
 
 //pub use template_app::TemplateApp;
+#[cfg(feature = "gui")]
 pub use game_app::GameApp as TemplateApp;
 
 pub use rand_xoshiro::Xoshiro128PlusPlus as Rand;
\ No newline at end of file
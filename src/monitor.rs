@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use egui::text::LayoutJob;
+use egui::*;
+
+use crate::game::text::*;
+
+// size of the monitor in characters, lines longer than a row wrap
+pub const MONITOR_COLUMNS: usize = 100;
+pub const MONITOR_ROWS: usize = 32;
+pub const MONITOR_FONT_SIZE: f32 = 14.0;
+
+pub const INCONSOLATA: &'_ str = "Inconsolata";
+
+// egui's fonts with inconsolata first for monospace text, everything else is unchanged
+pub fn font_definitions() -> FontDefinitions {
+    let data = include_bytes!("../Inconsolata/Inconsolata-VariableFont_wdth,wght.ttf");
+
+    let mut fonts = FontDefinitions::default();
+    fonts.font_data.insert(INCONSOLATA.to_owned(), Arc::new(FontData::from_static(data)));
+    fonts.families.entry(FontFamily::Monospace).or_default().insert(0, INCONSOLATA.to_owned());
+    fonts
+}
+
+pub fn load_inconsolata(ctx: &Context) {
+    ctx.set_fonts(font_definitions());
+}
+
+// the monitor's size in points, `cell` is the size of one character
+pub fn grid_size(cell: Vec2) -> Vec2 {
+    vec2(cell.x * MONITOR_COLUMNS as f32, cell.y * MONITOR_ROWS as f32)
+}
+
+// one console line as a single label that wraps at the monitor's width, each span coloured by its style
+pub fn layout_line(visuals: &Visuals, line: &StyledLine, font: &FontId, cell_width: f32) -> LayoutJob {
+    let mut job = LayoutJob::default();
+    job.wrap.max_width = cell_width * MONITOR_COLUMNS as f32;
+    for span in &line.spans {
+        let mut format = TextFormat::simple(font.clone(), span_color(visuals, span.style));
+        if span.style == SpanStyle::Header {
+            // the variable font has no separate bold face, headers are underlined instead
+            format.underline = Stroke::new(1.0, format.color);
+        }
+        job.append(&span.text, 0.0, format);
+    }
+    job
+}
+
+pub fn span_color(visuals: &Visuals, style: SpanStyle) -> Color32 {
+    if let Some([r, g, b]) = style.rgb() {
+        return Color32::from_rgb(r, g, b);
+    }
+    match style {
+        SpanStyle::Header => visuals.strong_text_color(),
+        SpanStyle::Input => visuals.weak_text_color(),
+        _ => visuals.text_color(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::damage::DamageType;
+
+    #[test]
+    fn test_inconsolata_first() {
+        let fonts = font_definitions();
+        assert_eq!(fonts.families[&FontFamily::Monospace][0], INCONSOLATA);
+        assert!(fonts.font_data.contains_key(INCONSOLATA));
+        assert!(!fonts.families[&FontFamily::Proportional].iter().any(|name| name == INCONSOLATA));
+    }
+
+    #[test]
+    fn test_layout_line() {
+        let visuals = Visuals::dark();
+        let font = FontId::monospace(MONITOR_FONT_SIZE);
+        let line = StyledLine::header("Camp")
+            .with(" you take ", SpanStyle::Plain)
+            .with("12 fire", SpanStyle::Damage(DamageType::Fire))
+            .with(" > look", SpanStyle::Input);
+
+        let job = layout_line(&visuals, &line, &font, 8.0);
+        assert_eq!(job.wrap.max_width, 8.0 * MONITOR_COLUMNS as f32);
+        assert_eq!(job.text, line.text());
+
+        let colors = job.sections.iter().map(|section| section.format.color).collect::<Vec<_>>();
+        assert_eq!(
+            colors,
+            [visuals.strong_text_color(), visuals.text_color(), Color32::from_rgb(240, 80, 40), visuals.weak_text_color()],
+        );
+        assert_eq!(job.sections[0].format.underline, Stroke::new(1.0, visuals.strong_text_color()));
+        assert!(job.sections[1..].iter().all(|section| section.format.underline == Stroke::NONE));
+        assert!(job.sections.iter().all(|section| section.format.font_id == font));
+    }
+}
@@ -1,5 +1,8 @@
+use std::fmt;
 use std::ops::{Deref, DerefMut};
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 type INT = u16;
 
 const NONE_MARKER: INT = INT::MAX;
@@ -28,7 +31,7 @@ const fn to_index(num: SparseIndex) -> usize {
 
 type SparseIndex = INT;
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct SparseVec<T> {
     dense: Dense<T>,
     sparse: Vec<DenseIndex>,
@@ -165,6 +168,76 @@ impl<T> DerefMut for SparseVec<T> {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum SparseError {
+    OutOfRange { index: INT, max_size: INT },
+    Duplicate(INT),
+}
+
+impl fmt::Display for SparseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SparseError::OutOfRange { index, max_size } => {
+                write!(f, "index {index} is out of range for a sparse vector of size {max_size}")
+            }
+            SparseError::Duplicate(index) => write!(f, "index {index} is stored more than once"),
+        }
+    }
+}
+
+impl std::error::Error for SparseError {}
+
+impl<T> SparseVec<T> {
+    pub fn max_size(&self) -> INT {
+        self.sparse.len() as INT
+    }
+
+    /// Builds a sparse vector from `(index, value)` pairs, values are stored densely in the order they are given
+    pub fn from_pairs(max_size: INT, pairs: impl IntoIterator<Item = (INT, T)>) -> Result<Self, SparseError> {
+        let mut sparse = Self::new(max_size);
+        for (index, value) in pairs {
+            if index >= max_size {
+                return Err(SparseError::OutOfRange { index, max_size });
+            }
+            if sparse.contains(index) {
+                return Err(SparseError::Duplicate(index));
+            }
+            sparse.insert(index, value);
+        }
+        Ok(sparse)
+    }
+}
+
+// only the occupied slots are written out, the sparse array is rebuilt on load
+#[derive(Serialize)]
+struct SavedRef<'a, T> {
+    max_size: INT,
+    items: Vec<(INT, &'a T)>,
+}
+
+#[derive(Deserialize)]
+struct Saved<T> {
+    max_size: INT,
+    items: Vec<(INT, T)>,
+}
+
+impl<T: Serialize> Serialize for SparseVec<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SavedRef {
+            max_size: self.max_size(),
+            items: self.iter().map(|(value, index)| (*index, value)).collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for SparseVec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let saved = Saved::deserialize(deserializer)?;
+        Self::from_pairs(saved.max_size, saved.items).map_err(serde::de::Error::custom)
+    }
+}
+
 // goofy code deduplication for SparseVec::sort
 fn set_dense_index(
     dense_indexes: &mut Vec<DenseIndex>,
@@ -316,7 +389,7 @@ impl<'a, T> VacantEntry<'a, T> {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 struct Dense<T> {
     items: Vec<Item<T>>,
 }
@@ -440,4 +513,27 @@ mod tests {
         assert_eq!(sparse.get(1), None);
         assert_eq!(sparse.get_mut(1), None);
     }
+
+    #[test]
+    fn test_serde() {
+        let mut sparse = SparseVec::new(8);
+        sparse.insert(5, 50);
+        sparse.insert(2, 20);
+        sparse.insert(7, 70);
+        sparse.remove(5);
+
+        let text = ron::to_string(&sparse).unwrap();
+        assert_eq!(text, "(max_size:8,items:[(7,70),(2,20)])");
+
+        let loaded: SparseVec<i32> = ron::from_str(&text).unwrap();
+        assert_eq!(loaded.max_size(), 8);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get(2), Some(&20));
+        assert_eq!(loaded.get(5), None);
+        assert_eq!(loaded.get(7), Some(&70));
+
+        assert!(ron::from_str::<SparseVec<i32>>("(max_size:2,items:[(2,0)])").is_err());
+        assert!(ron::from_str::<SparseVec<i32>>("(max_size:2,items:[(1,0),(1,1)])").is_err());
+        assert_eq!(SparseVec::from_pairs(2, [(1, 0), (1, 1)]).unwrap_err(), SparseError::Duplicate(1));
+    }
 }
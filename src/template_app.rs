@@ -1,20 +1,74 @@
 use crate::Rand;
+use crate::game::affix::AffixPool;
+use crate::game::ailment::tick_ailments;
+use crate::game::buff::Buff;
+use crate::game::combat::*;
+use crate::game::damage::DamageType;
+use crate::game::dps::{sample_attacks, Distribution};
+use crate::game::item::WeaponBase;
+use crate::game::item::{EquipSlot, Rarity};
+use crate::game::monster::{Bestiary, Monster};
+use crate::game::passive::NodeKind;
+use crate::game::sim::*;
+use crate::game::skill::*;
 use crate::game::stats::*;
+use crate::game::{Player, CRIT_CHANCE_MAX_VALUE};
 use rand::prelude::*;
 use egui_extras::{TableBuilder, Column};
 
+const FIGHT_TIME_LIMIT: i32 = 5 * 60 * 1000;
+
+const DISTRIBUTION_ATTACKS: usize = 10_000;
+const DISTRIBUTION_BINS: usize = 40;
+
+// the rng, combatants and logs are saved between runs, data files are always loaded fresh
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct TemplateApp {
     rand: Rand,
-    player: crate::game::Player,
-    attack_log: String,
+    #[serde(skip)]
+    affixes: AffixPool,
+    player: Player,
+    dummy: Player,
+    #[serde(skip)]
+    bestiary: Bestiary,
+    monster: Monster,
+    #[serde(skip)]
+    skills: SkillBook,
+    attack_log: Option<HitReport>,
+    #[serde(skip)]
+    skill_error: Option<String>,
+    fight_log: Option<String>,
+
+    // resampled whenever the player's stats or weapon change while the window is open
+    show_distribution: bool,
+    #[serde(skip)]
+    distribution: Option<(Vec<i32>, WeaponBase, Distribution)>,
 }
 
 impl Default for TemplateApp {
     fn default() -> Self {
+        let bestiary = Bestiary::builtin();
+        let skills = SkillBook::builtin();
+
+        let mut player = Player::new();
+        for (slot, skill) in skills.skills.iter().take(SKILL_BAR_SLOTS).enumerate() {
+            player.set_skill(slot, Some(skill.clone())).expect("only as many skills as there are slots");
+        }
+
         Self {
             rand: Rand::seed_from_u64(0),
-            player: crate::game::Player::new(),
-            attack_log: String::new(),
+            affixes: AffixPool::builtin(),
+            player,
+            dummy: Player::new(),
+            monster: bestiary.monsters[0].spawn(1, Rarity::Normal),
+            bestiary,
+            skills,
+            attack_log: None,
+            skill_error: None,
+            fight_log: None,
+            show_distribution: false,
+            distribution: None,
         }
     }
 }
@@ -27,8 +81,8 @@ impl TemplateApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(_storage) = cc.storage {
-            //return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        if let Some(storage) = cc.storage {
+            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
         }
 
         Default::default()
@@ -37,8 +91,8 @@ impl TemplateApp {
 
 impl eframe::App for TemplateApp {
     /// Called by the frame work to save state before shutdown.
-    fn save(&mut self, _storage: &mut dyn eframe::Storage) {
-        //eframe::set_value(storage, eframe::APP_KEY, self);
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, self);
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
@@ -65,45 +119,244 @@ impl eframe::App for TemplateApp {
             });
         });
 
+        // buffs count down in real time
+        if !self.player.buffs().is_empty() {
+            let elapsed = ctx.input(|input| input.stable_dt * 1000.0) as i32;
+            self.player.tick_buffs(elapsed);
+            ctx.request_repaint();
+        }
+
+        // and so do skill cooldowns
+        if self.player.skill_bar().is_cooling_down() {
+            let elapsed = ctx.input(|input| input.stable_dt * 1000.0) as i32;
+            self.player.tick_skills(elapsed);
+            ctx.request_repaint();
+        }
+
+        // ailments deal their damage over time in real time too
+        let ailing = [
+            (self.player.ailments(), self.player.debuffs()),
+            (self.dummy.ailments(), self.dummy.debuffs()),
+            (self.monster.ailments(), self.monster.debuffs()),
+        ];
+        if ailing.iter().any(|(ailments, debuffs)| ailments.iter().next().is_some() || debuffs.iter().next().is_some()) {
+            let elapsed = ctx.input(|input| input.stable_dt * 1000.0) as i32;
+            tick_ailments(&mut self.player, elapsed);
+            tick_ailments(&mut self.dummy, elapsed);
+            tick_ailments(&mut self.monster, elapsed);
+            self.player.debuffs_mut().tick(elapsed);
+            self.dummy.debuffs_mut().tick(elapsed);
+            self.monster.debuffs_mut().tick(elapsed);
+            ctx.request_repaint();
+        }
+
+        egui::Window::new("Passive Tree").show(ctx, |ui| {
+            passive_tree_ui(ui, &mut self.player);
+        });
+
+        egui::Window::new("Damage Distribution")
+            .open(&mut self.show_distribution)
+            .show(ctx, |ui| {
+                distribution_ui(ui, &self.player, &mut self.distribution);
+            });
+
         egui::CentralPanel::default().show(ctx, |ui| {
             // The central panel the region left after adding TopPanel's and SidePanel's
             ui.heading("RPG Stat Playground");
 
+            // stats are grouped by category, each group starts with a header row
+            let rows = StatCategory::ALL.into_iter()
+                .flat_map(|category| {
+                    let stats = ALL_STATS.iter().filter(move |stat| stat.category() == category).map(|&stat| StatRow::Stat(stat));
+                    std::iter::once(StatRow::Header(category)).chain(stats)
+                })
+                .collect::<Vec<_>>();
+
             TableBuilder::new(ui)
                 .max_scroll_height(400.0)
-                .column(Column::initial(150.0).at_least(100.0))
+                .column(Column::initial(250.0).at_least(100.0))
+                .column(Column::initial(100.0).at_least(100.0))
+                .column(Column::initial(100.0).at_least(100.0))
                 .column(Column::initial(100.0).at_least(100.0))
                 .header(20.0, |mut header| {
                     header.col(|ui| {
                         ui.heading("Stat Name");
                     });
                     header.col(|ui| {
-                        ui.heading("Stat Value");
+                        ui.heading("Base Value");
+                    });
+                    header.col(|ui| {
+                        ui.heading("Final Value");
+                    });
+                    header.col(|ui| {
+                        ui.heading("Dummy Value");
                     });
                 })
                 .body(|body| {
-                    body.rows(20.0, ALL_STATS.len(), |mut row| {
-                        let index = row.index();
-                        let stat = ALL_STATS.get(index).unwrap();
+                    body.rows(20.0, rows.len(), |mut row| {
+                        let stat = match rows[row.index()] {
+                            StatRow::Header(category) => {
+                                row.col(|ui| {
+                                    ui.strong(category.name());
+                                });
+                                return;
+                            }
+                            StatRow::Stat(stat) => stat,
+                        };
+                        row.col(|ui| {
+                            ui.label(stat.display_name())
+                                .on_hover_text(format!("{}\n\n{}", stat.name(), stat.description()));
+                        });
                         row.col(|ui| {
-                            ui.label(stat.name());
+                            base_stat_ui(ui, &mut self.player, stat);
                         });
                         row.col(|ui| {
-                            let val = self.player.stats.debug_get_mut(*stat);
-                            let drag_value = egui::DragValue::new(val)
-                                .update_while_editing(false);
-                            ui.add(drag_value);
+                            let response = ui.label(stat.format().value(self.player.stats().get(stat)));
+                            let contributions = self.player.contributions(stat);
+                            if !contributions.is_empty() {
+                                response.on_hover_ui(|ui| {
+                                    for (source, value) in contributions {
+                                        ui.label(format!("{}: {}", source.name(), stat.format().value(value)));
+                                    }
+                                });
+                            }
+                        });
+                        row.col(|ui| {
+                            base_stat_ui(ui, &mut self.dummy, stat);
                         });
                     })
                 });
             
             ui.separator();
 
-            if ui.button("Simulate Attack").clicked() {
-                self.attack_log = self.player.default_attack_test(&mut self.rand);
+            ui.horizontal(|ui| {
+                if ui.button("Simulate Attack").clicked() {
+                    self.attack_log = Some(self.player.default_attack_test(&mut self.rand));
+                }
+                if ui.button(format!("Simulate {} Attacks", DISTRIBUTION_ATTACKS)).clicked() {
+                    self.show_distribution = true;
+                }
+                if ui.button("Attack Target Dummy").clicked() {
+                    self.attack_log = Some(resolve_hit(&self.player, &mut self.dummy, &mut self.rand));
+                }
+                if ui.button("Reset Dummy").clicked() {
+                    self.dummy.restore_pools();
+                    self.dummy.ailments_mut().clear();
+                    self.dummy.debuffs_mut().clear();
+                }
+                if ui.button("Reroll Weapon").clicked() {
+                    let affixes = &self.affixes;
+                    let rand = &mut self.rand;
+                    self.player.edit_item(EquipSlot::MainHand, |item| {
+                        affixes.roll_item(item, Rarity::Rare, rand);
+                    });
+                }
+            });
+
+            ui.horizontal(|ui| {
+                if ui.button("Onslaught").clicked() {
+                    self.player.add_buff(Buff::new("Onslaught", vec![(AttackSpeed, 20), (MoveSpeed, 20)], Some(4000)));
+                }
+                if ui.button("Rage").clicked() {
+                    self.player.add_buff(Buff::new("Rage", vec![(DamageInc, 25)], Some(10000)));
+                }
+                if ui.button("Clear Buffs").clicked() {
+                    let names = self.player.buffs().iter().map(|buff| buff.name.clone()).collect::<Vec<_>>();
+                    for name in names {
+                        self.player.remove_buff(&name);
+                    }
+                }
+                for buff in self.player.buffs() {
+                    match buff.remaining {
+                        Some(remaining) => ui.label(format!("{} ({:.1}s)", buff.name, remaining as f32 / 1000.0)),
+                        None => ui.label(&buff.name),
+                    };
+                }
+            });
+
+            ui.horizontal(|ui| {
+                monster_ui(ui, &self.bestiary, &mut self.monster);
+                if ui.button("Attack Monster").clicked() {
+                    self.attack_log = Some(resolve_hit(&self.player, &mut self.monster, &mut self.rand));
+                }
+                if ui.button("Monster Attacks").clicked() {
+                    self.attack_log = Some(resolve_hit(&self.monster, &mut self.player, &mut self.rand));
+                }
+                if ui.button("Simulate Fight").clicked() {
+                    let seed = self.rand.gen();
+                    let mut encounter = Encounter::new(&mut self.player, &mut self.monster, seed);
+                    encounter.run(FIGHT_TIME_LIMIT);
+                    self.fight_log = Some(fight_summary(&encounter, seed));
+                }
+                if ui.button("Restore Pools").clicked() {
+                    self.monster.restore_pools();
+                    self.monster.ailments_mut().clear();
+                    self.monster.debuffs_mut().clear();
+                    self.player.restore_pools();
+                    self.player.ailments_mut().clear();
+                    self.player.debuffs_mut().clear();
+                }
+            });
+
+            for slot in 0..SKILL_BAR_SLOTS {
+                ui.horizontal(|ui| {
+                    skill_slot_ui(ui, &self.skills, &mut self.player, slot);
+                    let usable = self.player.skill_bar().get(slot).is_some();
+                    if ui.add_enabled(usable, egui::Button::new("Use on Monster")).clicked() {
+                        match cast_skill(&mut self.player, slot, &mut self.monster, &mut self.rand) {
+                            Ok(report) => {
+                                self.skill_error = None;
+                                if report.is_some() {
+                                    self.attack_log = report;
+                                }
+                            }
+                            Err(err) => self.skill_error = Some(err.to_string()),
+                        }
+                    }
+                });
+            }
+            if let Some(err) = &self.skill_error {
+                ui.label(err);
             }
 
-            ui.label(&self.attack_log);
+            if let Some(item) = self.player.slot(EquipSlot::MainHand) {
+                ui.label(format!("{} {} (item level {})", item.rarity.name(), item.name, item.level));
+                for affix in &item.affixes {
+                    let modifiers = affix.modifiers.iter()
+                        .map(|(stat, value)| stat.describe(*value))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    ui.label(format!("{:?} \"{}\": {}", affix.kind, affix.name, modifiers));
+                }
+            }
+
+            if let Some(summary) = &self.fight_log {
+                ui.label(summary);
+            }
+
+            if let Some(report) = &self.attack_log {
+                hit_report_ui(ui, report, self.player.main_hand_name());
+            }
+
+            let pools = self.dummy.pools();
+            ui.label(format!(
+                "Target dummy has {} energy shield and {} health.{}",
+                pools.energy_shield, pools.health, ailments_text(&self.dummy),
+            ));
+            let pools = self.monster.pools();
+            ui.label(format!(
+                "{} has {} energy shield and {} health.{}",
+                self.monster.name, pools.energy_shield, pools.health, ailments_text(&self.monster),
+            ));
+            let pools = self.player.pools();
+            ui.label(format!(
+                "Player has {} energy shield, {} health, {} mana and {} unreserved spirit.{}",
+                pools.energy_shield,
+                pools.health,
+                pools.mana,
+                self.player.unreserved_spirit(),
+                ailments_text(&self.player),
+            ));
 
             ui.separator();
 
@@ -115,6 +368,390 @@ impl eframe::App for TemplateApp {
     }
 }
 
+enum StatRow {
+    Header(StatCategory),
+    Stat(Stat),
+}
+
+// edit a player's base stat, the player's final stats are recalculated on change
+fn base_stat_ui(ui: &mut egui::Ui, player: &mut Player, stat: Stat) {
+    let mut value = player.base_stat(stat);
+    let drag_value = egui::DragValue::new(&mut value)
+        .update_while_editing(false);
+    if ui.add(drag_value).changed() {
+        player.set_base_stat(stat, value);
+    }
+}
+
+fn distribution_ui(ui: &mut egui::Ui, player: &Player, cache: &mut Option<(Vec<i32>, WeaponBase, Distribution)>) {
+    let stats = ALL_STATS.iter().map(|stat| player.stats().get(*stat)).collect::<Vec<_>>();
+    let weapon = player.main_hand_weapon();
+
+    // the same seed is used every time so changes come from the stats and not from the rolls
+    let stale = cache.as_ref().is_none_or(|(cached_stats, cached_weapon, _)| *cached_stats != stats || *cached_weapon != weapon);
+    if stale {
+        *cache = Some((stats, weapon, sample_attacks(player, DISTRIBUTION_ATTACKS, 0)));
+    }
+    let Some((_, _, distribution)) = cache else {
+        return;
+    };
+
+    ui.label(format!(
+        "Crit rate is {:.2}%, against a crit chance of {:.2}%.",
+        distribution.crit_rate(),
+        distribution.crit_chance.min(CRIT_CHANCE_MAX_VALUE) as f32 / 100.0,
+    ));
+    ui.label(format!(
+        "Damage ranges from {} to {}, with a mean of {:.2} and a variance of {:.2} (standard deviation {:.2}).",
+        distribution.min(),
+        distribution.max(),
+        distribution.mean(),
+        distribution.variance(),
+        distribution.variance().sqrt(),
+    ));
+
+    let width = distribution.bin_width(DISTRIBUTION_BINS);
+    let bars = distribution.histogram(DISTRIBUTION_BINS)
+        .into_iter()
+        .map(|(start, count)| egui_plot::Bar::new(start + width / 2.0, count as f64).width(width))
+        .collect();
+
+    egui_plot::Plot::new("damage_distribution")
+        .height(250.0)
+        .x_axis_label("Damage")
+        .y_axis_label("Attacks")
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(egui_plot::BarChart::new(bars).name("Attacks"));
+        });
+}
+
+fn fight_summary(encounter: &Encounter<'_, Player, Monster>, seed: u64) -> String {
+    let side_name = |side| match side {
+        Side::Left => "Player".to_owned(),
+        Side::Right => encounter.right.name.clone(),
+    };
+
+    let mut text = format!("Fight with seed {} lasted {:.2}s", seed, encounter.time() as f32 / 1000.0);
+    match encounter.winner() {
+        Some(side) => text += &format!(", {} won.", side_name(side)),
+        None => text += " without a winner.",
+    }
+
+    for side in [Side::Left, Side::Right] {
+        let hits = encounter.log.iter().filter_map(|event| match &event.kind {
+            SimEventKind::Hit(report) if event.side == side => Some(report),
+            _ => None,
+        });
+        let (mut attacks, mut landed, mut damage) = (0, 0, 0);
+        for report in hits {
+            attacks += 1;
+            if let Some(mitigation) = &report.mitigation {
+                landed += 1;
+                damage += mitigation.taken.total();
+            }
+        }
+        text += &format!(
+            "\n{} ({}) attacked {} times, landed {} hits and dealt {} damage.",
+            side_name(side),
+            side.name(),
+            attacks,
+            landed,
+            damage,
+        );
+    }
+    text
+}
+
+// pick a monster, level and rarity, the monster is respawned when any of them change
+fn monster_ui(ui: &mut egui::Ui, bestiary: &Bestiary, monster: &mut Monster) {
+    let mut name = monster.name.clone();
+    let mut level = monster.level;
+    let mut rarity = monster.rarity;
+
+    egui::ComboBox::from_id_salt("monster")
+        .selected_text(&name)
+        .show_ui(ui, |ui| {
+            for def in &bestiary.monsters {
+                ui.selectable_value(&mut name, def.name.clone(), &def.name);
+            }
+        });
+    ui.add(egui::DragValue::new(&mut level).range(1..=100).prefix("Level "));
+    egui::ComboBox::from_id_salt("monster_rarity")
+        .selected_text(rarity.name())
+        .show_ui(ui, |ui| {
+            for option in [Rarity::Normal, Rarity::Magic, Rarity::Rare, Rarity::Unique] {
+                ui.selectable_value(&mut rarity, option, option.name());
+            }
+        });
+
+    if name != monster.name || level != monster.level || rarity != monster.rarity {
+        if let Some(def) = bestiary.get(&name) {
+            *monster = def.spawn(level, rarity);
+        }
+    }
+}
+
+// pick the skill in a skill bar slot and show whether it can be used
+fn skill_slot_ui(ui: &mut egui::Ui, book: &SkillBook, player: &mut Player, slot: usize) {
+    let current = player.skill_bar().get(slot).map(|slot| slot.skill.name.clone());
+    let mut name = current.clone();
+
+    egui::ComboBox::from_id_salt(("skill", slot))
+        .selected_text(name.as_deref().unwrap_or("Empty"))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut name, None, "Empty");
+            for skill in &book.skills {
+                ui.selectable_value(&mut name, Some(skill.name.clone()), &skill.name);
+            }
+        });
+    if name != current {
+        player.set_skill(slot, name.and_then(|name| book.get(&name).cloned())).expect("the ui only shows existing slots");
+    }
+
+    let Some(slot) = player.skill_bar().get(slot) else {
+        return;
+    };
+    let skill = &slot.skill;
+    let mut info = Vec::new();
+    if !skill.tags.is_empty() {
+        info.push(skill.tags.iter().map(|tag| format!("{tag:?}")).collect::<Vec<_>>().join(", "));
+    }
+    if skill.is_persistent() {
+        info.push(format!("reserves {} spirit", skill.reservation));
+        info.push(if slot.active { "on" } else { "off" }.to_owned());
+    } else {
+        info.push(format!("{}% effectiveness", skill.effectiveness));
+        info.push(format!("{} mana", skill.mana_cost));
+        if let Some(time) = skill.action_time(player) {
+            info.push(format!("{:.2}s", time as f32 / 1000.0));
+        }
+    }
+    if slot.cooldown > 0 {
+        info.push(format!("ready in {:.1}s", slot.cooldown as f32 / 1000.0));
+    }
+    ui.label(info.join(", "));
+}
+
+// left click allocates a node, right click refunds it
+fn passive_tree_ui(ui: &mut egui::Ui, player: &mut Player) {
+    ui.horizontal(|ui| {
+        let tree = player.passive_tree();
+        ui.label(format!("{} points spent, {} left", tree.points_spent(), tree.points_left()));
+        if ui.button("Reset").clicked() {
+            player.reset_passives();
+        }
+    });
+
+    let tree = player.passive_tree();
+    let (response, painter) = ui.allocate_painter(egui::vec2(500.0, 450.0), egui::Sense::click());
+    let center = response.rect.center();
+    let position = |id| {
+        let [x, y] = tree.node(id).map(|node| node.position).unwrap_or_default();
+        center + egui::vec2(x, y)
+    };
+
+    let allocated_color = egui::Color32::GOLD;
+    let inactive_color = ui.visuals().weak_text_color();
+
+    for node in tree.nodes() {
+        for other in tree.neighbours(node.id).filter(|other| *other > node.id) {
+            let color = if tree.is_allocated(node.id) && tree.is_allocated(other) {
+                allocated_color
+            } else {
+                inactive_color
+            };
+            painter.line_segment([position(node.id), position(other)], (2.0, color));
+        }
+    }
+
+    let pointer = response.hover_pos();
+    let mut hovered = None;
+    for node in tree.nodes() {
+        let radius = match node.kind {
+            NodeKind::Start => 12.0,
+            NodeKind::Small => 8.0,
+            NodeKind::Notable => 12.0,
+            NodeKind::Keystone => 16.0,
+        };
+        let color = if tree.is_allocated(node.id) {
+            allocated_color
+        } else if tree.can_allocate(node.id).is_ok() {
+            ui.visuals().text_color()
+        } else {
+            inactive_color
+        };
+        painter.circle(position(node.id), radius, ui.visuals().extreme_bg_color, (2.0, color));
+
+        if pointer.is_some_and(|pointer| pointer.distance(position(node.id)) <= radius) {
+            hovered = Some(node.id);
+        }
+    }
+
+    let Some(id) = hovered else {
+        return;
+    };
+
+    if response.clicked() {
+        // nodes that can't be allocated are just ignored, the tooltip says why
+        let _ = player.allocate_passive(id);
+    } else if response.secondary_clicked() {
+        let _ = player.refund_passive(id);
+    }
+
+    let tree = player.passive_tree();
+    let node = tree.node(id).unwrap();
+    let status = if tree.is_allocated(id) {
+        tree.can_refund(id).err()
+    } else {
+        tree.can_allocate(id).err()
+    };
+    response.on_hover_ui_at_pointer(|ui| {
+        ui.strong(&node.name);
+        for (stat, value) in &node.stats {
+            ui.label(stat.describe(*value));
+        }
+        for modifier in &node.mods {
+            ui.label(modifier.describe());
+        }
+        for conditional in &node.conditional {
+            ui.label(conditional.describe());
+        }
+        if let Some(err) = status {
+            ui.weak(err.to_string());
+        }
+    });
+}
+
+fn hit_report_ui(ui: &mut egui::Ui, report: &HitReport, weapon_name: &str) {
+    let damage_types = || DamageType::ALL.into_iter().filter(|kind| !report.base_damage[*kind].is_zero());
+    let ranges = |crit| {
+        damage_types()
+            .map(|kind| {
+                let range = report.damage_range(kind, crit);
+                format!("{} to {} {}", range.min, range.max, kind.name())
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    ui.label(format!(
+        "{} deals {} damage.",
+        weapon_name,
+        damage_types()
+            .map(|kind| {
+                let range = report.base_damage[kind];
+                let more = match report.more[kind] - 100 {
+                    0 => String::new(),
+                    more if more < 0 => format!(", {}% less", -more),
+                    more => format!(", {more}% more"),
+                };
+                format!("{} to {} {} ({}% increased{})", range.min, range.max, kind.name(), report.increased[kind], more)
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+    ));
+    ui.label(format!(
+        "Base Critical Hit Chance is {:.2}% after player modifiers, for a final critical hit chance of {:.2}%.",
+        report.base_crit_chance as f32 / 100.0,
+        report.crit_chance as f32 / 100.0,
+    ));
+    ui.label(format!(
+        "The attack will deal {}, or {} with a Critical Hit's {}% Critical Damage Bonus.",
+        ranges(false),
+        ranges(true),
+        report.crit_damage_bonus,
+    ));
+
+    if !report.hit {
+        ui.label(format!(
+            "The attack was evaded, rolling {:.2} against a {:.2}% chance to hit.",
+            report.hit_roll as f32 / 100.0,
+            report.hit_chance as f32 / 100.0,
+        ));
+        return;
+    }
+
+    ui.label(format!(
+        "The attack hit with a {:.2}% chance to hit, and was a {} with a crit roll of {:.2}.",
+        report.hit_chance as f32 / 100.0,
+        if report.crit { "Critical Hit" } else { "Non-Critical Hit" },
+        report.crit_roll as f32 / 100.0,
+    ));
+
+    egui::Grid::new("hit_report").striped(true).show(ui, |ui| {
+        ui.strong("Damage Type");
+        ui.strong("Rolled");
+        if report.mitigation.is_some() {
+            ui.strong("Mitigated");
+            ui.strong("Taken");
+        }
+        ui.end_row();
+
+        for kind in damage_types() {
+            ui.label(kind.name());
+            ui.label(report.damage[kind].to_string());
+            if let Some(mitigation) = &report.mitigation {
+                match kind {
+                    DamageType::Physical => ui.label(format!("{}% armour", mitigation.armour_reduction)),
+                    _ => ui.label(format!("{}% resist", mitigation.resists[kind])),
+                };
+                ui.label(mitigation.taken[kind].to_string());
+            }
+            ui.end_row();
+        }
+    });
+
+    if let Some(mitigation) = &report.mitigation {
+        ui.label(format!(
+            "The target lost {} energy shield and {} health{}.",
+            mitigation.energy_shield_lost,
+            mitigation.health_lost,
+            if mitigation.killed { ", and was killed" } else { "" },
+        ));
+        for ailment in &mitigation.ailments {
+            ui.label(format!(
+                "The hit inflicted {} with a magnitude of {} for {:.1}s.",
+                ailment.kind.name(),
+                ailment.magnitude,
+                ailment.duration as f32 / 1000.0,
+            ));
+        }
+        for debuff in &mitigation.debuffs {
+            ui.label(format!(
+                "The hit inflicted {} for {:.1}s.",
+                debuff.kind.name(),
+                debuff.remaining as f32 / 1000.0,
+            ));
+        }
+    }
+}
+
+fn ailments_text(combatant: &impl Combatant) -> String {
+    let ailments = combatant.ailments();
+    let mut text = String::new();
+    for ailment in ailments.iter() {
+        text += &format!(" {} ({:.1}s)", ailment.kind.name(), ailment.remaining() as f32 / 1000.0);
+    }
+    for debuff in combatant.debuffs().iter() {
+        text += &format!(" {} ({:.1}s)", debuff.kind.name(), debuff.remaining as f32 / 1000.0);
+    }
+    if combatant.debuffs().slow() > 0 {
+        text += &format!(" Moves at {}% speed.", move_speed(combatant));
+    }
+    if ailments.shock() > 0 {
+        text += &format!(" Takes {}% increased damage.", ailments.shock());
+    }
+    if ailments.is_frozen() {
+        text += " Frozen.";
+    } else if ailments.chill() > 0 {
+        text += &format!(" Acts {}% slower.", ailments.chill());
+    }
+    text
+}
+
 fn powered_by_egui_and_eframe(ui: &mut egui::Ui) {
     ui.horizontal(|ui| {
         ui.spacing_mut().item_spacing.x = 0.0;